@@ -0,0 +1,356 @@
+use crate::{
+    network::QXWZAccount,
+    ntrip::{NtripCaster, NtripError, NtripVersion},
+};
+use serde::Deserialize;
+use std::{
+    collections::{hash_map::RandomState, HashMap},
+    hash::{BuildHasher, Hasher},
+    io,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+/// 账号或密码错误后暂停使用的时间
+const UNAUTHORIZED_PAUSE: Duration = Duration::from_secs(600);
+
+/// 账号来源
+///
+/// 每次连接前调用，因此账号可以在运行中修改。
+pub trait AccountProvider: 'static + Send {
+    /// 要连接的服务
+    fn casters(&self) -> Result<Vec<NtripCaster>, ConfigError>;
+}
+
+/// 从环境变量读取账号
+///
+/// 以 `QXWZ` 前缀为例，读取 `QXWZ_USER`、`QXWZ_PASSWORD`，
+/// 以及可选的 `QXWZ_CASTER`（`host:port`）、`QXWZ_MOUNTPOINT` 和 `QXWZ_VERSION`。
+#[derive(Clone, Debug)]
+pub struct EnvAccount(pub String);
+
+/// 从配置文件读取账号
+///
+/// 扩展名为 `.json` 时按 JSON 解析，否则按 TOML 解析，不认识的键报错：
+///
+/// ```toml
+/// caster = "203.107.45.154:8002"
+/// mountpoint = "AUTO"
+/// user = "user"
+/// password = "password"
+/// version = 1
+/// ```
+///
+/// 多个账号写成 `[[account]]` 表，表中未写的键沿用顶层的值；
+/// JSON 的结构相同，多个账号写在 `account` 数组中。
+#[derive(Clone, Debug)]
+pub struct ConfigFile(pub PathBuf);
+
+/// 读取账号失败的原因
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(io::Error),
+    Toml(toml::de::Error),
+    Json(serde_json::Error),
+    /// 第几个账号（从 0 开始）缺少用户名或密码，或服务器地址、协议版本无效
+    Account(usize),
+}
+
+/// 配置中的一个账号，也是配置文件的顶层
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct Account {
+    /// `host:port`，未指定时使用千寻
+    caster: Option<String>,
+    mountpoint: Option<String>,
+    user: Option<String>,
+    password: Option<String>,
+    /// NTRIP 协议版本，1 或 2
+    version: Option<u8>,
+    /// 账号池，只在顶层有效
+    #[serde(default)]
+    account: Vec<Account>,
+}
+
+impl Default for EnvAccount {
+    #[inline]
+    fn default() -> Self {
+        Self("QXWZ".into())
+    }
+}
+
+impl<T: QXWZAccount> AccountProvider for T {
+    fn casters(&self) -> Result<Vec<NtripCaster>, ConfigError> {
+        Ok(T::get_all().into_iter().map(T::caster).collect())
+    }
+}
+
+/// 运行时指定的账号
+impl AccountProvider for NtripCaster {
+    fn casters(&self) -> Result<Vec<NtripCaster>, ConfigError> {
+        Ok(vec![self.clone()])
+    }
+}
+
+/// 运行时指定的账号池
+impl AccountProvider for Vec<NtripCaster> {
+    fn casters(&self) -> Result<Vec<NtripCaster>, ConfigError> {
+        Ok(self.clone())
+    }
+}
+
+impl AccountProvider for EnvAccount {
+    fn casters(&self) -> Result<Vec<NtripCaster>, ConfigError> {
+        let var = |key: &str| std::env::var(format!("{}_{}", self.0, key)).ok();
+        let account = Account {
+            caster: var("CASTER"),
+            mountpoint: var("MOUNTPOINT"),
+            user: var("USER"),
+            password: var("PASSWORD"),
+            version: match var("VERSION") {
+                Some(version) => Some(version.parse().map_err(|_| ConfigError::Account(0))?),
+                None => None,
+            },
+            account: vec![],
+        };
+        account
+            .build()
+            .map(|caster| vec![caster])
+            .ok_or(ConfigError::Account(0))
+    }
+}
+
+impl ConfigFile {
+    /// 读取并解析配置文件
+    pub fn load(&self) -> Result<Vec<NtripCaster>, ConfigError> {
+        let text = std::fs::read_to_string(&self.0).map_err(ConfigError::Io)?;
+        let account: Account = if self.0.extension().is_some_and(|e| e == "json") {
+            serde_json::from_str(&text).map_err(ConfigError::Json)?
+        } else {
+            toml::from_str(&text).map_err(ConfigError::Toml)?
+        };
+        account.pool()
+    }
+}
+
+impl AccountProvider for ConfigFile {
+    #[inline]
+    fn casters(&self) -> Result<Vec<NtripCaster>, ConfigError> {
+        self.load()
+    }
+}
+
+impl Account {
+    /// 展开账号池，没有 `account` 时只有顶层一个账号
+    fn pool(mut self) -> Result<Vec<NtripCaster>, ConfigError> {
+        if self.account.is_empty() {
+            return self
+                .build()
+                .map(|caster| vec![caster])
+                .ok_or(ConfigError::Account(0));
+        }
+        std::mem::take(&mut self.account)
+            .into_iter()
+            .enumerate()
+            .map(|(i, account)| {
+                // 账号池不能嵌套
+                let caster = if account.account.is_empty() {
+                    account.inherit(&self).build()
+                } else {
+                    None
+                };
+                caster.ok_or(ConfigError::Account(i))
+            })
+            .collect()
+    }
+
+    /// 未写的键沿用 `top` 的值
+    fn inherit(self, top: &Self) -> Self {
+        Self {
+            caster: self.caster.or_else(|| top.caster.clone()),
+            mountpoint: self.mountpoint.or_else(|| top.mountpoint.clone()),
+            user: self.user.or_else(|| top.user.clone()),
+            password: self.password.or_else(|| top.password.clone()),
+            version: self.version.or(top.version),
+            account: vec![],
+        }
+    }
+
+    /// 构造服务，未指定服务器时使用千寻
+    fn build(&self) -> Option<NtripCaster> {
+        let mut caster = NtripCaster::qxwz(String::new());
+        if let Some(address) = &self.caster {
+            let (host, port) = address.rsplit_once(':')?;
+            caster.host = host.into();
+            caster.port = port.parse().ok()?;
+        }
+        if let Some(mountpoint) = &self.mountpoint {
+            caster.mountpoint = mountpoint.clone();
+        }
+        match self.version {
+            None | Some(1) => {}
+            Some(2) => caster.version = NtripVersion::V2,
+            Some(_) => return None,
+        }
+        Some(caster.with_login(self.user.as_ref()?, self.password.as_ref()?))
+    }
+}
+
+/// 多账号故障转移
+///
+/// 每个会话从账号池中随机的位置开始依次尝试，使共用账号池的设备分散到不同账号上。
+/// 账号或密码错误时暂停使用该账号一段时间，其他错误直接尝试下一个。
+#[derive(Debug)]
+pub(crate) struct Failover {
+    start: usize,
+    paused: HashMap<NtripCaster, Instant>,
+}
+
+impl Default for Failover {
+    fn default() -> Self {
+        Self {
+            start: RandomState::new().build_hasher().finish() as usize,
+            paused: HashMap::new(),
+        }
+    }
+}
+
+impl Failover {
+    /// 从本会话的起始位置轮转账号池，并去掉暂停中的账号
+    pub fn available(&mut self, mut casters: Vec<NtripCaster>) -> Vec<NtripCaster> {
+        let now = Instant::now();
+        self.paused.retain(|_, until| *until > now);
+        if !casters.is_empty() {
+            let len = casters.len();
+            casters.rotate_left(self.start % len);
+        }
+        casters
+            .into_iter()
+            .filter(|caster| !self.paused.contains_key(caster))
+            .collect()
+    }
+
+    pub fn fail(&mut self, caster: &NtripCaster, e: &NtripError) {
+        if let NtripError::Unauthorized = e {
+            self.paused
+                .insert(caster.clone(), Instant::now() + UNAUTHORIZED_PAUSE);
+        }
+    }
+}
+
+#[cfg(test)]
+mod t {
+    use super::*;
+
+    fn toml(text: &str) -> Result<Vec<NtripCaster>, ConfigError> {
+        toml::from_str::<Account>(text)
+            .map_err(ConfigError::Toml)?
+            .pool()
+    }
+
+    fn json(text: &str) -> Result<Vec<NtripCaster>, ConfigError> {
+        serde_json::from_str::<Account>(text)
+            .map_err(ConfigError::Json)?
+            .pool()
+    }
+
+    #[test]
+    fn test_config() {
+        let pool = toml(
+            "# 千寻 = 1\ncaster = \"rtk.example.com:2101\"\nmountpoint = 'RTCM32' # 挂载点\nuser = \"user\"\npassword = \"pa=ss\"\nversion = 2\n",
+        )
+        .unwrap();
+        assert_eq!(
+            pool,
+            json(
+                r#"{"caster": "rtk.example.com:2101", "mountpoint": "RTCM32", "user": "user", "password": "pa=ss", "version": 2}"#,
+            )
+            .unwrap()
+        );
+        let caster = &pool[0];
+        assert_eq!("rtk.example.com", caster.host);
+        assert_eq!(2101, caster.port);
+        assert_eq!("RTCM32", caster.mountpoint);
+        assert_eq!(NtripVersion::V2, caster.version);
+        assert_eq!(Some(base64::encode("user:pa=ss")), caster.auth);
+
+        // 转义和代理对
+        let pool = json(r#"{"user": "u", "password": "p\"a\\s\u0073\ud83d\ude00"}"#).unwrap();
+        assert_eq!(Some(base64::encode("u:p\"a\\ss\u{1f600}")), pool[0].auth);
+        assert_eq!(
+            pool,
+            toml(
+                r#"user = "u"
+password = "p\"a\\s\u0073\U0001F600""#
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(matches!(toml("user = \"a\""), Err(ConfigError::Account(0))));
+        assert!(matches!(
+            toml("user = \"a\"\npassword = \"1\"\nversion = 3"),
+            Err(ConfigError::Account(0))
+        ));
+        assert!(matches!(
+            toml("user = \"a\"\npassword = \"1\"\nport = 1"),
+            Err(ConfigError::Toml(_))
+        ));
+        assert!(matches!(
+            toml("user = \"a\"\nuser = \"b\""),
+            Err(ConfigError::Toml(_))
+        ));
+        assert!(matches!(
+            toml("[[account]]\nuser = \"a\"\npassword = \"1\"\n[[account.account]]"),
+            Err(ConfigError::Account(0))
+        ));
+        assert!(matches!(
+            json(r#"{"user": "\U0001F600", "password": "1"}"#),
+            Err(ConfigError::Json(_))
+        ));
+        assert!(matches!(
+            json(r#"{"user": "a", "password": 1}"#),
+            Err(ConfigError::Json(_))
+        ));
+    }
+
+    #[test]
+    fn test_pool() {
+        let pool = toml(
+            "mountpoint = \"AUTO\"\n[[account]]\nuser = \"a\"\npassword = \"1\"\nmountpoint = \"RTCM32\"\n[[account]]\nuser = \"b\"\npassword = \"2\"\n",
+        )
+        .unwrap();
+        assert_eq!(
+            pool,
+            toml(
+                "mountpoint = \"AUTO\"\naccount = [{ user = \"a\", password = \"1\", mountpoint = \"RTCM32\" }, { user = \"b\", password = \"2\" }]",
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            pool,
+            json(
+                r#"{"mountpoint": "AUTO", "account": [{"user": "a", "password": "1", "mountpoint": "RTCM32"}, {"user": "b", "password": "2"}]}"#,
+            )
+            .unwrap()
+        );
+        assert_eq!("RTCM32", pool[0].mountpoint);
+        assert_eq!("AUTO", pool[1].mountpoint);
+
+        let mut failover = Failover {
+            start: 1,
+            paused: HashMap::new(),
+        };
+        let available = failover.available(pool.clone());
+        assert_eq!(Some("b".into()), available[0].user());
+        assert_eq!(Some("a".into()), available[1].user());
+
+        failover.fail(&pool[0], &NtripError::Unauthorized);
+        failover.fail(&pool[1], &NtripError::Timeout);
+        let available = failover.available(pool.casters().unwrap());
+        assert_eq!(1, available.len());
+        assert_eq!(Some("b".into()), available[0].user());
+    }
+}
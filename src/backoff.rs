@@ -0,0 +1,60 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    time::Duration,
+};
+
+/// 带随机抖动的指数退避
+///
+/// 多台设备同时断线时，抖动避免它们在同一时刻重连。
+#[derive(Clone, Debug)]
+pub struct Backoff {
+    min: Duration,
+    max: Duration,
+    current: Duration,
+}
+
+impl Default for Backoff {
+    #[inline]
+    fn default() -> Self {
+        Self::new(Duration::from_secs(1), Duration::from_secs(60))
+    }
+}
+
+impl Backoff {
+    #[inline]
+    pub const fn new(min: Duration, max: Duration) -> Self {
+        Self {
+            min,
+            max,
+            current: min,
+        }
+    }
+
+    /// 下一次重试前等待的时间，在当前间隔的一半到全部之间随机，然后间隔加倍
+    pub fn next_delay(&mut self) -> Duration {
+        let half = self.current / 2;
+        let jitter = RandomState::new().build_hasher().finish() as f64 / u64::MAX as f64;
+        let delay = half + half.mul_f64(jitter);
+        self.current = (self.current * 2).min(self.max);
+        delay
+    }
+
+    /// 连接成功后恢复最小间隔
+    #[inline]
+    pub fn reset(&mut self) {
+        self.current = self.min;
+    }
+}
+
+#[test]
+fn test_backoff() {
+    let second = Duration::from_secs(1);
+    let mut backoff = Backoff::new(second, second * 4);
+    for max in [1, 2, 4, 4] {
+        let delay = backoff.next_delay();
+        assert!(second * max / 2 <= delay && delay <= second * max);
+    }
+    backoff.reset();
+    assert!(backoff.next_delay() <= second);
+}
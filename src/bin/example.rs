@@ -1,52 +1,10 @@
-﻿use async_std::{
-    net::UdpSocket,
-    sync::{Arc, Mutex},
-    task,
-};
-use driver::{SupervisorEventForSingle::*, SupervisorForSingle};
+﻿use async_std::{net::UdpSocket, sync::Arc, task};
 use gnss::{Enu, LocalReference, WGS84};
 use monitor_tool::{palette, rgba, vertex, Encoder, Shape, Vertex};
-use rtk_qxwz::{
-    AuthFile, Gpgga, GpggaParseError::*, GpggaSender, GpggaStatus::*, QXWZService, RTCMReceiver,
-    RTKBoard,
-};
+use rtk_qxwz::{AuthFile, GpggaStatus::*, RtkSession, SessionEvent::*};
 use std::time::Duration;
 
 fn main() {
-    let sender: Arc<Mutex<Option<GpggaSender>>> = Arc::new(Mutex::new(None));
-    let receiver: Arc<Mutex<Option<RTCMReceiver>>> = Arc::new(Mutex::new(None));
-    {
-        let sender = sender.clone();
-        let receiver = receiver.clone();
-        task::spawn_blocking(move || {
-            SupervisorForSingle::<QXWZService<AuthFile>>::default().join(|e| {
-                task::block_on(async {
-                    match e {
-                        Connected(_, stream) => {
-                            eprintln!("qxwz connected");
-                            *sender.lock().await = Some(stream.get_sender());
-                        }
-                        Disconnected => {
-                            eprintln!("qxwz disconnected");
-                            *sender.lock().await = None;
-                        }
-                        Event(_, Some((_, buf))) => {
-                            if let Some(ref mut receiver) = *receiver.lock().await {
-                                receiver.receive(buf.as_slice());
-                            }
-                        }
-                        Event(_, None) => {}
-                        ConnectFailed => {
-                            eprintln!("qxwz connect failed");
-                            task::sleep(Duration::from_secs(3)).await;
-                        }
-                    }
-                });
-                true
-            });
-        });
-    }
-
     let reference = LocalReference::from(WGS84 {
         latitude: 39.595678,
         longitude: 116.196329,
@@ -56,52 +14,45 @@ fn main() {
     let _ = task::block_on(socket.connect("127.0.0.1:12345"));
     send_config(socket.clone(), Duration::from_secs(3));
 
-    SupervisorForSingle::<RTKBoard>::default().join(|e| {
-        task::block_on(async {
-            match e {
-                Connected(port, board) => {
-                    eprintln!("Port = COM{}", port);
-                    *receiver.lock().await = Some(board.get_receiver());
-                }
-                Disconnected => {
-                    eprintln!("Serial disconnected.");
-                    *receiver.lock().await = None;
-                }
-                Event(_, Some((_, line))) => match line.parse::<Gpgga>() {
-                    Ok(gpgga) => {
-                        if let Some(ref mut sender) = *sender.lock().await {
-                            sender.send(&line).await;
-                        }
-                        println!("{:?}", gpgga);
-                        let enu = reference.wgs84_to_enu(WGS84 {
-                            latitude: gpgga.latitude,
-                            longitude: gpgga.longitude,
-                            altitude: gpgga.altitude,
-                        });
-                        match gpgga.status {
-                            无效解 | 用户输入 | 航位推算 | PPS | PPP => {}
-                            单点解 => paint(&socket, 0, enu).await,
-                            伪距差分 => paint(&socket, 1, enu).await,
-                            浮点解 => paint(&socket, 2, enu).await,
-                            固定解 => paint(&socket, 3, enu).await,
-                        }
-                    }
-                    Err(WrongHead) => {}
-                    Err(_) => {
-                        if let Some(ref mut sender) = *sender.lock().await {
-                            sender.send(&line).await;
-                        }
+    for e in RtkSession::new(AuthFile).spawn() {
+        match e {
+            CasterConnected(_) => eprintln!("qxwz connected"),
+            CasterDisconnected => eprintln!("qxwz disconnected"),
+            CasterNoData(_) => eprintln!("qxwz disconnected without data"),
+            CasterConnectFailed(Some(e)) => eprintln!("qxwz connect failed: {:?}", e),
+            CasterConnectFailed(None) => eprintln!("qxwz connect failed"),
+            AccountFailed(e) => eprintln!("failed to read account: {:?}", e),
+            BoardConnected(port) => eprintln!("Port = COM{}", port),
+            BoardDisconnected => eprintln!("Serial disconnected."),
+            BoardConnectFailed => eprintln!("Serial failed to connect."),
+            Board(event) => {
+                let gpgga = match event.gpgga() {
+                    Some(gpgga) => gpgga,
+                    None => continue,
+                };
+                println!("{:?}", gpgga);
+                let (latitude, longitude, altitude) =
+                    match (gpgga.latitude, gpgga.longitude, gpgga.altitude) {
+                        (Some(lat), Some(lon), Some(alt)) => (lat, lon, alt),
+                        _ => continue,
+                    };
+                let enu = reference.wgs84_to_enu(WGS84 {
+                    latitude,
+                    longitude,
+                    altitude,
+                });
+                task::block_on(async {
+                    match gpgga.status {
+                        无效解 | 用户输入 | 航位推算 | PPS | PPP => {}
+                        单点解 => paint(&socket, 0, enu).await,
+                        伪距差分 => paint(&socket, 1, enu).await,
+                        浮点解 => paint(&socket, 2, enu).await,
+                        固定解 => paint(&socket, 3, enu).await,
                     }
-                },
-                Event(_, None) => {}
-                ConnectFailed => {
-                    eprintln!("Serial failed to connect.");
-                    task::sleep(Duration::from_secs(1)).await;
-                }
+                });
             }
-        });
-        true
-    });
+        }
+    }
 }
 
 fn send_config(socket: Arc<UdpSocket>, period: Duration) {
@@ -0,0 +1,233 @@
+use crate::ubx;
+
+/// 板卡厂商
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Vendor {
+    Unicore,
+    Ublox,
+    Novatel,
+}
+
+/// 板卡配置命令
+#[derive(Clone, Debug)]
+pub enum BoardCommand {
+    /// Unicore 文本命令，如 `GPGGA COM1 1`，应答 `$command,...,response: OK`
+    Unicore(String),
+    /// NovAtel ASCII 命令，如 `LOG COM1 GPGGA ONTIME 1`，应答 `<OK`
+    Novatel(String),
+    /// UBX 配置消息，应答 ACK-ACK 或 ACK-NAK
+    Ubx { class: u8, id: u8, payload: Vec<u8> },
+}
+
+/// 配置命令失败的原因
+#[derive(Debug)]
+pub enum CommandError {
+    /// 串口读写失败
+    Disconnected,
+    /// 等待应答超时
+    Timeout,
+    /// 板卡拒绝了命令，附带应答内容
+    Rejected(String),
+}
+
+impl BoardCommand {
+    /// 设置 NMEA 语句的输出周期（秒）
+    ///
+    /// u-blox 的周期以导航解算次数为单位，按 1 Hz 导航频率换算；
+    /// 端口为 `DDC`、`UART1`、`UART2`、`USB` 或 `SPI`，CFG-MSG 同时设置所有端口，其他端口关闭该语句，
+    /// 端口为空时只设置接收命令的端口。
+    pub fn nmea_rate(vendor: Vendor, sentence: &str, port: &str, period: f32) -> Option<Self> {
+        match vendor {
+            Vendor::Unicore => Some(Self::Unicore(format!("{} {} {}", sentence, port, period))),
+            Vendor::Novatel => Some(Self::Novatel(if period > 0.0 {
+                format!("LOG {} {} ONTIME {}", port, sentence, period)
+            } else {
+                format!("UNLOG {} {}", port, sentence)
+            })),
+            Vendor::Ublox => {
+                let id = match sentence.get(2..)? {
+                    "GGA" => 0x00,
+                    "GLL" => 0x01,
+                    "GSA" => 0x02,
+                    "GSV" => 0x03,
+                    "RMC" => 0x04,
+                    "VTG" => 0x05,
+                    "GST" => 0x07,
+                    "ZDA" => 0x08,
+                    "GNS" => 0x0d,
+                    _ => return None,
+                };
+                let rate = period.round() as u8;
+                let payload = match port {
+                    "" => vec![0xf0, id, rate],
+                    port => {
+                        let i = ["DDC", "UART1", "UART2", "USB", "SPI"]
+                            .iter()
+                            .position(|p| *p == port)?;
+                        let mut payload = vec![0xf0, id, 0, 0, 0, 0, 0, 0];
+                        payload[2 + i] = rate;
+                        payload
+                    }
+                };
+                Some(Self::Ubx {
+                    class: 0x06,
+                    id: 0x01,
+                    payload,
+                })
+            }
+        }
+    }
+
+    /// 设置接收 RTCM 3 差分数据的端口
+    ///
+    /// Unicore 板卡自动识别各端口的输入格式，无需配置，返回 `None`。
+    /// u-blox 的端口为 `UART1`、`UART2` 或 `USB`，仅支持 M9 以上的 CFG-VALSET。
+    pub fn rtcm_input(vendor: Vendor, port: &str) -> Option<Self> {
+        match vendor {
+            Vendor::Unicore => None,
+            Vendor::Novatel => Some(Self::Novatel(format!(
+                "INTERFACEMODE {} RTCMV3 NOVATEL ON",
+                port
+            ))),
+            Vendor::Ublox => {
+                let key: u32 = match port {
+                    "UART1" => 0x10730004,
+                    "UART2" => 0x10750004,
+                    "USB" => 0x10770004,
+                    _ => return None,
+                };
+                let mut payload = vec![0x00, 0x01, 0x00, 0x00];
+                payload.extend_from_slice(&key.to_le_bytes());
+                payload.push(1);
+                Some(Self::Ubx {
+                    class: 0x06,
+                    id: 0x8a,
+                    payload,
+                })
+            }
+        }
+    }
+
+    /// 保存当前配置
+    pub fn save(vendor: Vendor) -> Self {
+        match vendor {
+            Vendor::Unicore => Self::Unicore("SAVECONFIG".into()),
+            Vendor::Novatel => Self::Novatel("SAVECONFIG".into()),
+            Vendor::Ublox => Self::Ubx {
+                class: 0x06,
+                id: 0x09,
+                payload: vec![0, 0, 0, 0, 0xff, 0xff, 0, 0, 0, 0, 0, 0, 0x17],
+            },
+        }
+    }
+
+    /// 发送到串口的字节
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            Self::Unicore(text) | Self::Novatel(text) => format!("{}\r\n", text).into_bytes(),
+            Self::Ubx { class, id, payload } => ubx::encode(*class, *id, payload),
+        }
+    }
+
+    /// 在已收到的数据中查找应答，未找到返回 `None`
+    pub(crate) fn check(&self, received: &[u8]) -> Option<Result<(), CommandError>> {
+        match self {
+            // 应答中回显了命令，据此区分其他命令的应答
+            Self::Unicore(text) => lines(received)
+                .filter_map(|line| Some((line, line.strip_prefix("$command,")?)))
+                .find_map(|(line, rest)| {
+                    let (echo, response) = rest.split_once(",response:")?;
+                    if !same_command(echo, text) {
+                        return None;
+                    }
+                    Some(if response.trim_start().starts_with("OK") {
+                        Ok(())
+                    } else {
+                        Err(CommandError::Rejected(line.into()))
+                    })
+                }),
+            Self::Novatel(_) => lines(received).find_map(|line| {
+                if line.contains("<OK") {
+                    Some(Ok(()))
+                } else if line.contains("<ERROR") {
+                    Some(Err(CommandError::Rejected(line.into())))
+                } else {
+                    None
+                }
+            }),
+            Self::Ubx { class, id, .. } => received.windows(8).find_map(|w| match w {
+                [0xb5, 0x62, 0x05, ack, 0x02, 0x00, c, i] if c == class && i == id => {
+                    Some(if *ack == 0x01 {
+                        Ok(())
+                    } else {
+                        Err(CommandError::Rejected("UBX-ACK-NAK".into()))
+                    })
+                }
+                _ => None,
+            }),
+        }
+    }
+}
+
+/// 忽略大小写和多余空白比较命令
+fn same_command(a: &str, b: &str) -> bool {
+    a.split_whitespace()
+        .map(str::to_ascii_uppercase)
+        .eq(b.split_whitespace().map(str::to_ascii_uppercase))
+}
+
+/// 完整的文本行
+fn lines(received: &[u8]) -> impl Iterator<Item = &str> {
+    let end = received
+        .iter()
+        .rposition(|b| *b == b'\n')
+        .map_or(0, |i| i + 1);
+    received[..end]
+        .split(|b| *b == b'\n')
+        .filter_map(|line| std::str::from_utf8(line).ok())
+        .map(str::trim)
+}
+
+#[cfg(test)]
+mod t {
+    use super::*;
+
+    #[test]
+    fn test_check() {
+        let cmd = BoardCommand::nmea_rate(Vendor::Unicore, "GPGGA", "COM1", 1.0).unwrap();
+        assert_eq!(b"GPGGA COM1 1\r\n".to_vec(), cmd.to_bytes());
+        assert!(cmd.check(b"$GPGGA,,,,,,0,00,99.99,,,,,,*48\r\n").is_none());
+        assert!(cmd
+            .check(b"$command,GPRMC COM1 1,response: OK*2A\r\n")
+            .is_none());
+        assert!(cmd
+            .check(b"$command,GPGGA COM1 1,response: OK*2A\r\n")
+            .unwrap()
+            .is_ok());
+        assert!(matches!(
+            cmd.check(b"$command,GPGGA COM1 1,response: PARSING FAILD*2A\r\n"),
+            Some(Err(CommandError::Rejected(_)))
+        ));
+
+        let cmd = BoardCommand::save(Vendor::Novatel);
+        assert!(cmd.check(b"<OK").is_none());
+        assert!(cmd.check(b"[COM1]<OK\r\n").unwrap().is_ok());
+
+        let cmd = BoardCommand::nmea_rate(Vendor::Ublox, "GNGGA", "UART2", 1.0).unwrap();
+        assert!(matches!(
+            cmd,
+            BoardCommand::Ubx { ref payload, .. } if payload == &[0xf0, 0, 0, 0, 1, 0, 0, 0]
+        ));
+        assert!(BoardCommand::nmea_rate(Vendor::Ublox, "GNGGA", "COM1", 1.0).is_none());
+
+        let cmd = BoardCommand::nmea_rate(Vendor::Ublox, "GNGGA", "", 1.0).unwrap();
+        assert!(cmd
+            .check(&[0x24, 0xb5, 0x62, 0x05, 0x01, 0x02, 0x00, 0x06, 0x01, 0x0f, 0x38])
+            .unwrap()
+            .is_ok());
+        assert!(matches!(
+            cmd.check(&[0xb5, 0x62, 0x05, 0x00, 0x02, 0x00, 0x06, 0x01, 0x0e, 0x37]),
+            Some(Err(CommandError::Rejected(_)))
+        ));
+    }
+}
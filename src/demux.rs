@@ -0,0 +1,274 @@
+use crate::{
+    nmea::xor,
+    novatel::{self, crc32},
+    rtcm::crc24q,
+    ubx,
+};
+
+const MAX_NMEA_LEN: usize = 512;
+
+/// 从同一个字节流中分离 NMEA、UBX、NovAtel 二进制日志和 RTCM 3
+pub struct Demuxer<const LEN: usize> {
+    buf: [u8; LEN],
+    p_read: usize,
+    p_write: usize,
+    /// `buf[0]` 在整个字节流中的位置
+    base: u64,
+}
+
+/// 分离出的一帧
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Frame<'a> {
+    /// 校验正确的 NMEA 语句，不含行尾
+    Nmea(&'a str),
+    /// 完整的 UBX 帧，包括同步字和校验位
+    Ubx(&'a [u8]),
+    /// 完整的 NovAtel 二进制日志，包括帧头和校验位
+    Novatel(&'a [u8]),
+    /// 完整的 RTCM 3 帧，包括帧头和校验位
+    Rtcm(&'a [u8]),
+}
+
+enum Check {
+    Frame(usize),
+    Pending,
+    Invalid,
+}
+
+impl<const LEN: usize> Default for Demuxer<LEN> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const LEN: usize> Demuxer<LEN> {
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            buf: [0u8; LEN],
+            p_read: 0,
+            p_write: 0,
+            base: 0,
+        }
+    }
+
+    pub fn write_buf(&mut self) -> &mut [u8] {
+        if self.p_read > 0 {
+            self.buf.copy_within(self.p_read..self.p_write, 0);
+            self.p_write -= self.p_read;
+            self.base += self.p_read as u64;
+            self.p_read = 0;
+        }
+        &mut self.buf[self.p_write..]
+    }
+
+    #[inline]
+    pub fn extend(&mut self, n: usize) {
+        self.p_write += n;
+    }
+
+    /// 写入一段数据，返回实际写入的长度
+    pub fn push(&mut self, bytes: &[u8]) -> usize {
+        let buf = self.write_buf();
+        let n = bytes.len().min(buf.len());
+        buf[..n].copy_from_slice(&bytes[..n]);
+        self.extend(n);
+        n
+    }
+
+    /// 最近写入的 `n` 个字节，需在下次写入前读取
+    #[inline]
+    pub(crate) fn tail(&self, n: usize) -> &[u8] {
+        &self.buf[self.p_write - n..self.p_write]
+    }
+
+    /// 已写入的总字节数
+    #[inline]
+    pub fn received(&self) -> u64 {
+        self.base + self.p_write as u64
+    }
+
+    #[inline]
+    pub fn parse(&mut self) -> Option<Frame<'_>> {
+        self.parse_at().map(|(_, frame)| frame)
+    }
+
+    /// 解析一帧，同时返回帧头在整个字节流中的位置
+    pub fn parse_at(&mut self) -> Option<(u64, Frame<'_>)> {
+        loop {
+            let rest = &self.buf[self.p_read..self.p_write];
+            let check = match rest.first()? {
+                b'$' => check_nmea(rest),
+                0xb5 => check_ubx(rest, LEN),
+                0xaa => check_novatel(rest, LEN),
+                0xd3 => check_rtcm(rest, LEN),
+                _ => Check::Invalid,
+            };
+            match check {
+                Check::Frame(n) => {
+                    let position = self.base + self.p_read as u64;
+                    let frame = &self.buf[self.p_read..][..n];
+                    self.p_read += n;
+                    return Some((
+                        position,
+                        match frame[0] {
+                            b'$' => Frame::Nmea(unsafe { std::str::from_utf8_unchecked(frame) }),
+                            0xb5 => Frame::Ubx(frame),
+                            0xaa => Frame::Novatel(frame),
+                            _ => Frame::Rtcm(frame),
+                        },
+                    ));
+                }
+                // 缓冲区已满仍不能组成帧，说明起始位是假的
+                Check::Pending if self.p_read > 0 || self.p_write < LEN => return None,
+                Check::Pending | Check::Invalid => self.p_read += 1,
+            }
+        }
+    }
+}
+
+fn check_nmea(rest: &[u8]) -> Check {
+    let star = match rest.iter().take(MAX_NMEA_LEN).position(|b| *b == b'*') {
+        Some(i) => i,
+        None if rest.len() < MAX_NMEA_LEN => return Check::Pending,
+        None => return Check::Invalid,
+    };
+    if !rest[1..star].iter().all(|b| (0x20..0x7f).contains(b)) {
+        return Check::Invalid;
+    }
+    let cs = match rest.get(star + 1..star + 3) {
+        Some(cs) => cs,
+        None => return Check::Pending,
+    };
+    match std::str::from_utf8(cs)
+        .ok()
+        .and_then(|cs| u8::from_str_radix(cs, 16).ok())
+    {
+        Some(sum) if sum == xor(&rest[1..star]) => Check::Frame(star + 3),
+        _ => Check::Invalid,
+    }
+}
+
+fn check_ubx(rest: &[u8], capacity: usize) -> Check {
+    if rest.len() < 6 {
+        return match rest.get(1) {
+            Some(0x62) | None => Check::Pending,
+            Some(_) => Check::Invalid,
+        };
+    }
+    if rest[1] != 0x62 {
+        return Check::Invalid;
+    }
+    let len = 8 + u16::from_le_bytes([rest[4], rest[5]]) as usize;
+    if len > capacity {
+        Check::Invalid
+    } else if rest.len() < len {
+        Check::Pending
+    } else if ubx::checksum(&rest[2..len - 2]) == rest[len - 2..len] {
+        Check::Frame(len)
+    } else {
+        Check::Invalid
+    }
+}
+
+fn check_novatel(rest: &[u8], capacity: usize) -> Check {
+    let sync = &rest[..rest.len().min(3)];
+    if sync != &novatel::SYNC[..sync.len()] {
+        return Check::Invalid;
+    }
+    if rest.len() < 10 {
+        return Check::Pending;
+    }
+    let header = rest[3] as usize;
+    let len = header + u16::from_le_bytes([rest[8], rest[9]]) as usize + 4;
+    if header < novatel::HEADER_LEN || len > capacity {
+        Check::Invalid
+    } else if rest.len() < len {
+        Check::Pending
+    } else if crc32(&rest[..len - 4]).to_le_bytes() == rest[len - 4..len] {
+        Check::Frame(len)
+    } else {
+        Check::Invalid
+    }
+}
+
+fn check_rtcm(rest: &[u8], capacity: usize) -> Check {
+    if rest.len() < 3 {
+        return Check::Pending;
+    }
+    if rest[1] & 0xfc != 0 {
+        return Check::Invalid;
+    }
+    let len = 3 + (((rest[1] as usize) << 8) | rest[2] as usize) + 3;
+    // 缓冲区装不下的长度说明起始位是假的，不必等待
+    if len > capacity {
+        return Check::Invalid;
+    }
+    if rest.len() < len {
+        return Check::Pending;
+    }
+    let crc = &rest[len - 3..len];
+    if crc24q(&rest[..len - 3]) == u32::from_be_bytes([0, crc[0], crc[1], crc[2]]) {
+        Check::Frame(len)
+    } else {
+        Check::Invalid
+    }
+}
+
+#[test]
+fn test_demux() {
+    let nmea = b"$GPGGA,,,,,,0,00,99.99,,,,,,*48";
+    let ubx = ubx::encode(0x05, 0x01, &[0x06, 0x01]);
+    let mut rtcm = vec![0xd3, 0x00, 0x02, 0x3e, 0xd0];
+    let crc = crc24q(&rtcm);
+    rtcm.extend_from_slice(&crc.to_be_bytes()[1..]);
+    let mut log = vec![0xaa, 0x44, 0x12, 28];
+    log.resize(28, 0);
+    let crc = crc32(&log);
+    log.extend_from_slice(&crc.to_le_bytes());
+
+    let mut stream = Vec::new();
+    stream.extend_from_slice(b"\xff\xb5$GP");
+    stream.extend_from_slice(nmea);
+    stream.extend_from_slice(b"\r\n");
+    stream.extend_from_slice(&ubx);
+    stream.extend_from_slice(&rtcm);
+    stream.extend_from_slice(b"\xaa\x44");
+    stream.extend_from_slice(&log);
+    stream.extend_from_slice(nmea);
+
+    let mut demuxer = Demuxer::<256>::new();
+    let (head, tail) = stream.split_at(stream.len() - 10);
+    assert_eq!(head.len(), demuxer.push(head));
+    assert_eq!(
+        Some(Frame::Nmea(std::str::from_utf8(nmea).unwrap())),
+        demuxer.parse()
+    );
+    assert_eq!(Some(Frame::Ubx(&ubx)), demuxer.parse());
+    assert_eq!(Some(Frame::Rtcm(&rtcm)), demuxer.parse());
+    assert_eq!(Some(Frame::Novatel(&log)), demuxer.parse());
+    assert_eq!(None, demuxer.parse());
+    demuxer.push(tail);
+    assert_eq!(stream.len() as u64, demuxer.received());
+    assert_eq!(
+        Some((
+            (stream.len() - nmea.len()) as u64,
+            Frame::Nmea(std::str::from_utf8(nmea).unwrap())
+        )),
+        demuxer.parse_at()
+    );
+    assert_eq!(None, demuxer.parse());
+}
+
+#[test]
+fn test_false_rtcm() {
+    let nmea = b"$GPGGA,,,,,,0,00,99.99,,,,,,*48";
+    let mut demuxer = Demuxer::<64>::new();
+    demuxer.push(b"\xd3\x03\xff");
+    demuxer.push(nmea);
+    assert_eq!(
+        Some(Frame::Nmea(std::str::from_utf8(nmea).unwrap())),
+        demuxer.parse()
+    );
+}
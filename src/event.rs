@@ -0,0 +1,96 @@
+use crate::{
+    demux::Frame,
+    heading::Heading,
+    novatel::{BinaryLog, LogBody},
+    rtcm::RtcmMessage,
+    sentence::NmeaSentence,
+    ubx::UbxMessage,
+    Gpgga, GpggaParseError,
+};
+use std::time::Instant;
+
+/// 板卡输出的一帧数据
+#[derive(Debug)]
+pub struct BoardEvent {
+    /// 帧头的接收时间
+    pub time: Instant,
+    /// 原始数据，NMEA 语句包括行尾的 `\r\n`
+    pub raw: Vec<u8>,
+    pub message: BoardMessage,
+}
+
+/// 解析后的内容
+#[derive(Debug)]
+pub enum BoardMessage {
+    /// NMEA 语句，不支持的语句类型为 `Err(WrongHead)`
+    Nmea(Result<NmeaSentence, GpggaParseError>),
+    Ubx(UbxMessage),
+    Log(BinaryLog),
+    Rtcm(RtcmMessage),
+}
+
+impl BoardEvent {
+    /// 解析一帧，不能解析的二进制帧返回 `None`
+    pub(crate) fn decode(time: Instant, frame: Frame) -> Option<Self> {
+        let (raw, message) = match frame {
+            Frame::Nmea(line) => (
+                format!("{}\r\n", line).into_bytes(),
+                BoardMessage::Nmea(line.parse()),
+            ),
+            Frame::Ubx(frame) => (
+                frame.to_vec(),
+                BoardMessage::Ubx(UbxMessage::decode(frame)?),
+            ),
+            Frame::Novatel(frame) => (frame.to_vec(), BoardMessage::Log(BinaryLog::decode(frame)?)),
+            Frame::Rtcm(frame) => (
+                frame.to_vec(),
+                BoardMessage::Rtcm(RtcmMessage::decode(frame)?),
+            ),
+        };
+        Some(Self { time, raw, message })
+    }
+
+    /// NMEA 语句的原文，可直接转发给服务器
+    #[inline]
+    pub fn text(&self) -> Option<&str> {
+        match self.message {
+            BoardMessage::Nmea(_) => std::str::from_utf8(&self.raw).ok(),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn gpgga(&self) -> Option<&Gpgga> {
+        match &self.message {
+            BoardMessage::Nmea(Ok(NmeaSentence::Gga(gpgga))) => Some(gpgga),
+            _ => None,
+        }
+    }
+
+    /// 来自 HDT、HEADING 日志或 NAV-RELPOSNED 的定向结果
+    pub fn heading(&self) -> Option<Heading> {
+        match &self.message {
+            BoardMessage::Nmea(Ok(NmeaSentence::Hdt(hdt))) => Heading::from_hdt(hdt),
+            BoardMessage::Log(BinaryLog {
+                body: LogBody::Heading(log),
+                ..
+            }) => Some(Heading::from_log(log)),
+            BoardMessage::Ubx(UbxMessage::NavRelPosNed(rel)) => Some(Heading::from_relposned(rel)),
+            _ => None,
+        }
+    }
+}
+
+#[test]
+fn test_decode() {
+    let event = BoardEvent::decode(Instant::now(), Frame::Nmea("$GPHDT,274.07,T*03")).unwrap();
+    assert_eq!(Some("$GPHDT,274.07,T*03\r\n"), event.text());
+    assert!(event.gpgga().is_none());
+    assert_eq!(274.07, event.heading().unwrap().heading);
+
+    let event = BoardEvent::decode(Instant::now(), Frame::Nmea("$GPGLL,,,,,,V,N*64")).unwrap();
+    assert!(matches!(
+        event.message,
+        BoardMessage::Nmea(Err(GpggaParseError::WrongHead))
+    ));
+}
@@ -1,15 +1,59 @@
-﻿use std::str::FromStr;
+﻿use crate::{
+    nmea::{field, parse_degree, strip_checksum, xor},
+    NmeaTime,
+};
+use std::{fmt, str::FromStr};
 
 #[derive(Default, Debug)]
 pub struct Gpgga {
-    pub utc: f32,
-    pub latitude: f64,
-    pub longitude: f64,
+    pub talker: Talker,
+    pub utc: Option<NmeaTime>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
     pub status: GpggaStatus,
-    pub satellite: u8,
-    pub hdop: f32,
-    pub altitude: f64,
-    pub altitude_error: f64,
+    pub satellite: Option<u8>,
+    pub hdop: Option<f32>,
+    pub altitude: Option<f64>,
+    pub altitude_error: Option<f64>,
+    /// 差分龄期（秒）
+    pub differential_age: Option<f32>,
+    /// 差分参考站号
+    pub station_id: Option<u16>,
+    /// 解析时记录的小数位数，输出时沿用
+    pub digits: GpggaDigits,
+}
+
+/// 各数值字段的小数位数
+///
+/// 为 `None` 时经纬度的分保留 8 位小数，其余字段按最短形式输出。
+#[derive(PartialEq, Eq, Clone, Copy, Default, Debug)]
+pub struct GpggaDigits {
+    pub latitude: Option<u8>,
+    pub longitude: Option<u8>,
+    pub hdop: Option<u8>,
+    pub altitude: Option<u8>,
+    pub altitude_error: Option<u8>,
+    pub differential_age: Option<u8>,
+}
+
+/// 发出语句的卫星系统
+#[derive(PartialEq, Eq, Clone, Copy, Default, Debug)]
+pub enum Talker {
+    /// `GP`
+    #[default]
+    Gps,
+    /// `GL`
+    Glonass,
+    /// `GA`
+    Galileo,
+    /// `GB`
+    BeiDou,
+    /// `GQ`
+    Qzss,
+    /// `GN`，多系统联合
+    Multi,
+    /// 其他两个大写字母的发送方，如 `GI` 或北斗的旧式 `BD`，`P` 开头的专有语句除外
+    Other([u8; 2]),
 }
 
 #[derive(PartialEq, Eq, Clone, Copy, Debug)]
@@ -25,83 +69,72 @@ pub enum GpggaStatus {
     PPP = 8,
 }
 
+#[derive(Debug)]
 pub enum GpggaParseError {
     WrongHead,
     LackOfField(&'static str),
     FailToParse(&'static str),
 }
 
-macro_rules! field {
-    ($name:expr; $body:ident) => {
-        if let Some(word) = $body.next() {
-            if let Ok(val) = word.parse() {
-                val
-            } else {
-                return Err(FailToParse($name));
-            }
-        } else {
-            return Err(LackOfField($name));
-        }
-    };
-    ($name:expr; $body:ident, $parse:expr) => {
-        if let Some(word) = $body.next() {
-            if let Some(val) = $parse(word) {
-                val
-            } else {
-                return Err(FailToParse($name));
-            }
-        } else {
-            return Err(LackOfField($name));
-        }
-    };
-}
-
 impl FromStr for Gpgga {
     type Err = GpggaParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         use GpggaParseError::*;
-        if let Some(body) = s.strip_prefix("$GPGGA,") {
+        let s = strip_checksum(s);
+        let talker = s.get(1..3).and_then(|t| t.parse().ok());
+        if let (Some(talker), Some(body)) =
+            (talker, s.get(3..).and_then(|s| s.strip_prefix("GGA,")))
+        {
             let mut body = body.split(',');
-            let mut result = Self::default();
+            let mut result = Self {
+                talker,
+                ..Default::default()
+            };
             // utc
-            result.utc = field!("utc"; body);
+            result.utc = field!("utc"; body?);
             // latitude
-            result.latitude = field!("latitude"; body, parse_degree);
+            (result.latitude, result.digits.latitude) = field!("latitude"; body?, degree).unzip();
             match body.next() {
-                Some("N") => {}
-                Some("S") => result.latitude = -result.latitude,
+                Some("N") | Some("") => {}
+                Some("S") => result.latitude = result.latitude.map(|v| -v),
                 Some(_) => return Err(FailToParse("latitude_dir")),
                 None => return Err(LackOfField("latitude_dir")),
             }
             // longitude
-            result.longitude = field!("longitude"; body, parse_degree);
+            (result.longitude, result.digits.longitude) =
+                field!("longitude"; body?, degree).unzip();
             match body.next() {
-                Some("E") => {}
-                Some("W") => result.longitude = -result.longitude,
+                Some("E") | Some("") => {}
+                Some("W") => result.longitude = result.longitude.map(|v| -v),
                 Some(_) => return Err(FailToParse("longitude_dir")),
                 None => return Err(LackOfField("longitude_dir")),
             }
             // status
             result.status = field!("status"; body);
             // satellite
-            result.satellite = field!("satellite"; body);
+            result.satellite = field!("satellite"; body?);
             // hdop
-            result.hdop = field!("hdop"; body);
+            (result.hdop, result.digits.hdop) = field!("hdop"; body?, number).unzip();
             // altitude
-            result.altitude = field!("altitude"; body);
+            (result.altitude, result.digits.altitude) = field!("altitude"; body?, number).unzip();
             match body.next() {
-                Some("M") => {}
+                Some("M") | Some("") => {}
                 Some(_) => return Err(FailToParse("altitude_unit")),
                 None => return Err(LackOfField("altitude_unit")),
             }
             // altitude_error
-            result.altitude_error = field!("altitude_error"; body);
+            (result.altitude_error, result.digits.altitude_error) =
+                field!("altitude_error"; body?, number).unzip();
             match body.next() {
-                Some("M") => {}
+                Some("M") | Some("") => {}
                 Some(_) => return Err(FailToParse("altitude_error_unit")),
                 None => return Err(LackOfField("altitude_error_unit")),
             }
+            // differential
+            (result.differential_age, result.digits.differential_age) =
+                field!("differential_age"; body?, number).unzip();
+            result.station_id = field!("station_id"; body?);
             Ok(result)
         } else {
             Err(WrongHead)
@@ -109,6 +142,97 @@ impl FromStr for Gpgga {
     }
 }
 
+impl fmt::Display for Gpgga {
+    /// 生成带校验和的语句，不含行尾
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use fmt::Write;
+        let mut body = format!("{}GGA,", self.talker);
+        if let Some(utc) = self.utc {
+            write!(body, "{}", utc)?;
+        }
+        match self.latitude {
+            Some(lat) => write!(
+                body,
+                ",{},{}",
+                Degree(lat, 2, self.digits.latitude),
+                if lat < 0.0 { 'S' } else { 'N' }
+            )?,
+            None => body += ",,",
+        }
+        match self.longitude {
+            Some(lon) => write!(
+                body,
+                ",{},{}",
+                Degree(lon, 3, self.digits.longitude),
+                if lon < 0.0 { 'W' } else { 'E' }
+            )?,
+            None => body += ",,",
+        }
+        write!(body, ",{},", self.status as u8)?;
+        if let Some(satellite) = self.satellite {
+            write!(body, "{:02}", satellite)?;
+        }
+        body.push(',');
+        if let Some(hdop) = self.hdop {
+            write!(body, "{}", Number(hdop, self.digits.hdop))?;
+        }
+        for (val, digits) in [
+            (self.altitude, self.digits.altitude),
+            (self.altitude_error, self.digits.altitude_error),
+        ] {
+            match val {
+                Some(val) => write!(body, ",{},M", Number(val, digits))?,
+                None => body += ",,",
+            }
+        }
+        body.push(',');
+        if let Some(age) = self.differential_age {
+            write!(body, "{}", Number(age, self.digits.differential_age))?;
+        }
+        body.push(',');
+        if let Some(id) = self.station_id {
+            write!(body, "{:04}", id)?;
+        }
+        write!(f, "${}*{:02X}", body, xor(body.as_bytes()))
+    }
+}
+
+impl fmt::Display for Talker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Gps => "GP",
+            Self::Glonass => "GL",
+            Self::Galileo => "GA",
+            Self::BeiDou => "GB",
+            Self::Qzss => "GQ",
+            Self::Multi => "GN",
+            // 解析时已保证是 ASCII 字母
+            Self::Other(id) => std::str::from_utf8(id).map_err(|_| fmt::Error)?,
+        })
+    }
+}
+
+impl FromStr for Talker {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "GP" => Ok(Self::Gps),
+            "GL" => Ok(Self::Glonass),
+            "GA" => Ok(Self::Galileo),
+            "GB" => Ok(Self::BeiDou),
+            "GQ" => Ok(Self::Qzss),
+            "GN" => Ok(Self::Multi),
+            _ => match *s.as_bytes() {
+                [a, b] if a != b'P' && a.is_ascii_uppercase() && b.is_ascii_uppercase() => {
+                    Ok(Self::Other([a, b]))
+                }
+                _ => Err(()),
+            },
+        }
+    }
+}
+
 impl Default for GpggaStatus {
     fn default() -> Self {
         Self::无效解
@@ -134,10 +258,161 @@ impl FromStr for GpggaStatus {
     }
 }
 
-/// 度分格式转十进制度
-fn parse_degree(word: &str) -> Option<f64> {
-    let split = word.find('.')? - 2;
-    let degrees = &word[..split].parse::<f64>().ok()?;
-    let minutes = &word[split..].parse::<f64>().ok()?;
-    Some(degrees + minutes / 60.0)
+/// 记录小数位数的十进制度
+fn degree(word: &str) -> Option<(f64, u8)> {
+    Some((parse_degree(word)?, digits(word)))
+}
+
+/// 记录小数位数的数值
+fn number<T: FromStr>(word: &str) -> Option<(T, u8)> {
+    Some((word.parse().ok()?, digits(word)))
+}
+
+/// 小数位数，超过 12 位的部分输出时舍去
+fn digits(word: &str) -> u8 {
+    word.split_once('.')
+        .map_or(0, |(_, fraction)| fraction.len().min(12) as u8)
+}
+
+/// 十进制度转度分格式，参数为度数、度的位数和分的小数位数
+struct Degree(f64, usize, Option<u8>);
+
+impl fmt::Display for Degree {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // 以分的最后一位小数为单位取整，避免分进位到 60
+        let digits = self.2.unwrap_or(8).min(12) as usize;
+        let scale = 10u64.pow(digits as u32);
+        let total = (self.0.abs() * 60.0 * scale as f64).round() as u64;
+        let (degrees, minutes) = (total / (60 * scale), total % (60 * scale));
+        write!(
+            f,
+            "{:0width$}{:02}",
+            degrees,
+            minutes / scale,
+            width = self.1
+        )?;
+        if digits > 0 {
+            write!(f, ".{:0digits$}", minutes % scale, digits = digits)?;
+        }
+        Ok(())
+    }
+}
+
+/// 按指定小数位数输出数值，未指定时按最短形式
+struct Number<T>(T, Option<u8>);
+
+impl<T: fmt::Display> fmt::Display for Number<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.1 {
+            Some(digits) => write!(f, "{:.*}", digits as usize, self.0),
+            None => write!(f, "{}", self.0),
+        }
+    }
+}
+
+#[test]
+fn test_talker() {
+    const BODY: &str =
+        "GGA,060220.00,3959.55874779,N,11619.61828897,E,4,17,1.6,60.1397,M,-9.2862,M,,*42";
+    for (head, talker) in [
+        ("$GP", Talker::Gps),
+        ("$GN", Talker::Multi),
+        ("$GB", Talker::BeiDou),
+        ("$GL", Talker::Glonass),
+        ("$GA", Talker::Galileo),
+        ("$GQ", Talker::Qzss),
+        ("$GI", Talker::Other(*b"GI")),
+        ("$BD", Talker::Other(*b"BD")),
+    ] {
+        match format!("{}{}", head, BODY).parse::<Gpgga>() {
+            Ok(gpgga) => {
+                assert_eq!(talker, gpgga.talker);
+                assert_eq!(GpggaStatus::固定解, gpgga.status);
+            }
+            Err(_) => panic!(),
+        }
+    }
+    assert_eq!("GI", Talker::Other(*b"GI").to_string());
+    assert_eq!("BD", Talker::Other(*b"BD").to_string());
+    assert!(matches!(
+        format!("$gp{}", BODY).parse::<Gpgga>(),
+        Err(GpggaParseError::WrongHead)
+    ));
+}
+
+#[test]
+fn test_differential() {
+    match "$GNGGA,060220.00,3959.55874779,N,11619.61828897,E,4,17,1.6,60.1397,M,-9.2862,M,1.0,0001*6C"
+        .parse::<Gpgga>()
+    {
+        Ok(gpgga) => {
+            assert_eq!(Some(1.0), gpgga.differential_age);
+            assert_eq!(Some(1), gpgga.station_id);
+        }
+        Err(_) => panic!(),
+    }
+    match "$GPGGA,060220.00,3959.55874779,N,11619.61828897,E,1,17,1.6,60.1397,M,-9.2862,M,,*42"
+        .parse::<Gpgga>()
+    {
+        Ok(gpgga) => {
+            assert_eq!(None, gpgga.differential_age);
+            assert_eq!(None, gpgga.station_id);
+        }
+        Err(_) => panic!(),
+    }
+}
+
+#[test]
+fn test_no_fix() {
+    match "$GPGGA,,,,,,0,00,99.99,,,,,,*48".parse::<Gpgga>() {
+        Ok(gpgga) => {
+            assert_eq!(GpggaStatus::无效解, gpgga.status);
+            assert_eq!(None, gpgga.utc);
+            assert_eq!(None, gpgga.latitude);
+            assert_eq!(None, gpgga.longitude);
+            assert_eq!(None, gpgga.altitude);
+            assert_eq!(Some(0), gpgga.satellite);
+        }
+        Err(_) => panic!(),
+    }
+    match "$GNGGA,,,,,,0,,,,,,,,*78".parse::<Gpgga>() {
+        Ok(gpgga) => {
+            assert_eq!(GpggaStatus::无效解, gpgga.status);
+            assert_eq!(None, gpgga.satellite);
+            assert_eq!(None, gpgga.hdop);
+        }
+        Err(_) => panic!(),
+    }
+}
+
+#[test]
+fn test_display() {
+    for line in [
+        "$GPGGA,060220.00,3959.55874779,N,11619.61828897,E,1,17,1.6,60.1397,M,-9.2862,M,,*42",
+        "$GNGGA,060220.00,3959.55874779,S,11619.61828897,W,4,17,1.6,60.1397,M,-9.2862,M,1,0001*66",
+        "$GPGGA,,,,,,0,00,99.99,,,,,,*48",
+        "$GNGGA,,,,,,0,,,,,,,,*78",
+        "$GPGGA,060220.125,3959.55874779,N,11619.61828897,E,1,17,1.6,60.1397,M,-9.2862,M,,*74",
+        "$GPGGA,060220.00,3959.5587,N,11619.6183,E,1,08,1.60,60.10,M,-9.3,M,2.0,0001*63",
+        "$BDGGA,060220.00,3959.55874779,N,11619.61828897,E,4,17,1.6,60.1397,M,-9.2862,M,,*56",
+    ] {
+        match line.parse::<Gpgga>() {
+            Ok(gpgga) => assert_eq!(line, gpgga.to_string()),
+            Err(_) => panic!(),
+        }
+    }
+
+    // 未记录位数时使用默认格式
+    let gpgga = Gpgga {
+        latitude: parse_degree("3959.5587"),
+        longitude: parse_degree("11619.6183"),
+        status: GpggaStatus::单点解,
+        hdop: Some(1.6),
+        altitude: Some(60.1),
+        ..Default::default()
+    };
+    assert_eq!(
+        "$GPGGA,,3959.55870000,N,11619.61830000,E,1,,1.6,60.1,M,,,,*2A",
+        gpgga.to_string()
+    );
 }
@@ -0,0 +1,104 @@
+use crate::{
+    novatel::{HeadingLog, PositionType},
+    sentence::Hdt,
+    ubx::NavRelPosNed,
+};
+
+/// 双天线定向结果
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Heading {
+    /// 主天线指向从天线的真航向（度）
+    pub heading: f32,
+    /// 俯仰角（度），从天线高于主天线为正
+    pub pitch: Option<f32>,
+    /// 基线长度（米）
+    pub length: Option<f32>,
+    /// 航向标准差（度）
+    pub heading_std: Option<f32>,
+    pub status: HeadingStatus,
+}
+
+/// 定向解状态
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum HeadingStatus {
+    /// 无效，航向不可用
+    Invalid,
+    /// 浮点解
+    Float,
+    /// 固定解
+    Fixed,
+    /// 来源未提供状态，如 HDT
+    Unknown,
+}
+
+impl Heading {
+    #[inline]
+    pub fn is_valid(&self) -> bool {
+        self.status != HeadingStatus::Invalid
+    }
+
+    /// HDT 的航向为空时返回 `None`
+    pub fn from_hdt(hdt: &Hdt) -> Option<Self> {
+        Some(Self {
+            heading: hdt.heading?,
+            pitch: None,
+            length: None,
+            heading_std: None,
+            status: HeadingStatus::Unknown,
+        })
+    }
+
+    pub fn from_log(log: &HeadingLog) -> Self {
+        let status = match log.position_type {
+            _ if log.solution_status != 0 => HeadingStatus::Invalid,
+            t if t.is_fixed() => HeadingStatus::Fixed,
+            t if t.is_float() => HeadingStatus::Float,
+            PositionType::None => HeadingStatus::Invalid,
+            _ => HeadingStatus::Unknown,
+        };
+        Self {
+            heading: log.heading,
+            pitch: Some(log.pitch),
+            length: Some(log.length),
+            heading_std: Some(log.heading_std),
+            status,
+        }
+    }
+
+    /// 版本 0 的 NAV-RELPOSNED 不含航向，由相对位置计算
+    pub fn from_relposned(rel: &NavRelPosNed) -> Self {
+        let [n, e, d] = rel.position;
+        let horizontal = n.hypot(e);
+        let status = match rel.carrier_solution {
+            _ if !rel.position_valid => HeadingStatus::Invalid,
+            _ if rel.length.is_some() && !rel.heading_valid => HeadingStatus::Invalid,
+            1 => HeadingStatus::Float,
+            2 => HeadingStatus::Fixed,
+            _ => HeadingStatus::Invalid,
+        };
+        Self {
+            heading: rel
+                .heading
+                .unwrap_or_else(|| e.atan2(n).to_degrees().rem_euclid(360.0) as f32),
+            pitch: Some((-d).atan2(horizontal).to_degrees() as f32),
+            length: Some(rel.length.unwrap_or_else(|| horizontal.hypot(d)) as f32),
+            heading_std: rel.heading_accuracy,
+            status,
+        }
+    }
+}
+
+#[test]
+fn test_relposned() {
+    let rel = NavRelPosNed {
+        position: [0.0, -1.0, -1.0],
+        position_valid: true,
+        carrier_solution: 2,
+        ..Default::default()
+    };
+    let heading = Heading::from_relposned(&rel);
+    assert_eq!(270.0, heading.heading);
+    assert_eq!(Some(45.0), heading.pitch);
+    assert_eq!(HeadingStatus::Fixed, heading.status);
+    assert!((heading.length.unwrap() - 2f32.sqrt()).abs() < 1e-6);
+}
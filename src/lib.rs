@@ -1,9 +1,43 @@
+mod account;
+mod backoff;
+mod command;
+mod demux;
+mod event;
 mod gpgga;
+mod heading;
 mod network;
 mod nmea;
+mod novatel;
+mod ntrip;
+mod port;
+mod rtcm;
+mod sentence;
 mod serial;
+mod session;
+mod sourcetable;
+mod time;
+mod ubx;
+mod upload;
 
+pub use account::{AccountProvider, ConfigError, ConfigFile, EnvAccount};
+pub use backoff::Backoff;
 pub use base64::encode as encode_base64;
-pub use gpgga::{Gpgga, GpggaParseError, GpggaStatus};
+pub use command::{BoardCommand, CommandError, Vendor};
+pub use demux::{Demuxer, Frame};
+pub use event::{BoardEvent, BoardMessage};
+pub use gpgga::{Gpgga, GpggaDigits, GpggaParseError, GpggaStatus, Talker};
+pub use heading::{Heading, HeadingStatus};
 pub use network::{AuthFile, GpggaSender, QXWZAccount, QXWZService};
-pub use serial::{RTCMReceiver, RTKBoard};
+pub use novatel::{BestPos, BinaryLog, HeadingLog, LogBody, PositionType, PsrVel};
+pub use ntrip::{NtripCaster, NtripError, NtripVersion};
+pub use port::{PortFilter, UsbInfo};
+pub use rtcm::{
+    AntennaDescriptor, Constellation, GlonassBiases, MsmHeader, RtcmMessage, RtcmStats, StationArp,
+};
+pub use sentence::{Gns, Gsa, Gst, Gsv, Hdt, NmeaDate, NmeaSentence, Rmc, SatelliteInfo, Vtg, Zda};
+pub use serial::{AutoBaud, Baud, BoardConfig, RTCMReceiver, RTKBoard, BAUD_RATES};
+pub use session::{ActiveAccount, RtkSession, SessionEvent};
+pub use sourcetable::{CasterRecord, NetworkRecord, SourceTable, StreamRecord};
+pub use time::{GnssClock, GpsTime, NmeaTime, UtcClock, LEAP_SECONDS};
+pub use ubx::{NavPvt, NavRelPosNed, NavSat, RxmRtcm, UbxMessage, UbxSatellite};
+pub use upload::{GpggaUploader, UPLOAD_INTERVAL};
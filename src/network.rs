@@ -1,20 +1,43 @@
-﻿use async_std::{
-    io::{prelude::BufReadExt, BufReader, ReadExt, WriteExt},
-    net::TcpStream,
-    task,
+﻿use crate::{
+    ntrip::{NtripCaster, NtripError, NtripStream},
+    rtcm::{Buffer, RtcmStats},
+    Gpgga,
 };
+use async_std::{future, io::WriteExt, net::TcpStream, task};
 use driver::Driver;
 use std::{
     marker::PhantomData,
     time::{Duration, Instant},
 };
 
-pub struct QXWZService<T>(TcpStream, PhantomData<T>);
+pub struct QXWZService<T = ()> {
+    stream: NtripStream,
+    buf: Buffer<2048>,
+    timeout: Duration,
+    _account: PhantomData<T>,
+}
+
+#[derive(Clone)]
+pub struct GpggaSender(pub(crate) TcpStream);
 
-pub struct GpggaSender(TcpStream);
+/// 默认的差分数据超时，超过这个时间没有收到差分数据视为断开
+pub(crate) const DATA_TIMEOUT: Duration = Duration::from_secs(10);
 
 pub trait QXWZAccount: 'static + Send {
     fn get() -> Option<String>;
+
+    /// 账号池，依次尝试，默认只有 [`get`](Self::get) 返回的一个
+    fn get_all() -> Vec<String> {
+        Self::get().into_iter().collect()
+    }
+
+    /// 账号对应的 CORS 服务，默认为千寻
+    fn caster(auth: String) -> NtripCaster {
+        NtripCaster::qxwz(auth)
+    }
+
+    /// [`Driver::new`] 连接失败时调用，默认忽略
+    fn connect_failed(_caster: &NtripCaster, _e: NtripError) {}
 }
 
 pub struct AuthFile;
@@ -27,41 +50,92 @@ impl QXWZAccount for AuthFile {
             .next()
             .map(|line| base64::encode(line))
     }
-}
 
-// const ASK: &str = "\
-// GET / HTTP/1.1\r\n\
-// Accept: */*\r\n\
-// \r\n";
-
-macro_rules! AUTH {
-    () => {
-        "\
-GET /AUTO HTTP/1.1\r\n\
-Authorization: Basic {}\r\n\
-\r\n"
-    };
+    /// 每行一个账号
+    fn get_all() -> Vec<String> {
+        std::fs::read_to_string("auth")
+            .unwrap_or_default()
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(base64::encode)
+            .collect()
+    }
 }
 
 impl GpggaSender {
     pub async fn send(&mut self, line: &str) {
         let _ = self.0.write_all(line.as_bytes()).await;
     }
+
+    /// 发送合成的位置
+    pub async fn send_gpgga(&mut self, gpgga: &Gpgga) {
+        self.send(&format!("{}\r\n", gpgga)).await;
+    }
+}
+
+impl QXWZService {
+    /// 连接指定的服务，用于不经过 [`QXWZAccount`] 的场合
+    ///
+    /// `timeout` 为差分数据超时，此后用 [`QXWZService::run`] 接收数据。
+    pub fn connect(caster: &NtripCaster, timeout: Duration) -> Result<Self, NtripError> {
+        task::block_on(NtripStream::connect(caster)).map(|stream| Self {
+            stream,
+            buf: Buffer::new(),
+            timeout,
+            _account: PhantomData,
+        })
+    }
 }
 
 impl<T> QXWZService<T> {
     pub fn get_sender(&self) -> GpggaSender {
-        GpggaSender(self.0.clone())
+        GpggaSender(self.stream.writer())
+    }
+
+    /// 差分数据帧统计
+    #[inline]
+    pub fn stats(&self) -> RtcmStats {
+        self.buf.stats()
+    }
+
+    /// 接收差分数据直到断开，与 [`Driver::join`] 相同
+    pub fn run<F>(&mut self, mut f: F) -> bool
+    where
+        F: FnMut(&mut Self, Option<(Instant, Vec<u8>)>) -> bool,
+    {
+        task::block_on(async move {
+            let mut time = Instant::now();
+            loop {
+                // 每次回调一个完整的帧
+                while let Some(frame) = self.buf.parse() {
+                    let frame = frame.to_vec();
+                    // 如果回调指示不要继续阻塞，立即退出
+                    if !f(self, Some((time, frame))) {
+                        return true;
+                    }
+                }
+                // 半开连接上的读取可能永远不会返回
+                let read = self.stream.read(self.buf.write_buf());
+                match future::timeout(self.timeout, read).await {
+                    Err(_) | Ok(None) => return false,
+                    Ok(Some(n)) => {
+                        time = Instant::now();
+                        self.buf.extend(n);
+                    }
+                }
+            }
+        })
     }
 }
 
 impl<T: QXWZAccount> Driver for QXWZService<T> {
     type Pacemaker = ();
-    type Key = String;
+    type Key = NtripCaster;
     type Event = Vec<u8>;
 
     fn keys() -> Vec<Self::Key> {
-        T::get().map(|a| vec![a]).unwrap_or_default()
+        T::get_all().into_iter().map(T::caster).collect()
     }
 
     fn open_timeout() -> std::time::Duration {
@@ -69,48 +143,29 @@ impl<T: QXWZAccount> Driver for QXWZService<T> {
     }
 
     fn new(t: &Self::Key) -> Option<(Self::Pacemaker, Self)> {
-        task::block_on(async move {
-            let auth = format!(AUTH!(), t);
-            let mut tcp = match TcpStream::connect("203.107.45.154:8002").await {
-                Ok(tcp) => tcp,
-                Err(_) => return None,
-            };
-            if let Err(_) = tcp.write_all(auth.as_bytes()).await {
-                return None;
+        match task::block_on(NtripStream::connect(t)) {
+            Ok(stream) => Some((
+                (),
+                Self {
+                    stream,
+                    buf: Buffer::new(),
+                    timeout: DATA_TIMEOUT,
+                    _account: PhantomData {},
+                },
+            )),
+            Err(e) => {
+                T::connect_failed(t, e);
+                None
             }
-            let mut line = String::new();
-            let mut reader = BufReader::new(tcp);
-            match reader.read_line(&mut line).await {
-                Ok(_) => {
-                    if line.trim() == "ICY 200 OK" {
-                        Some(((), Self(reader.into_inner(), PhantomData {})))
-                    } else {
-                        None
-                    }
-                }
-                Err(_) => None,
-            }
-        })
+        }
     }
 
-    fn join<F>(&mut self, mut f: F) -> bool
+    #[inline]
+    fn join<F>(&mut self, f: F) -> bool
     where
         F: FnMut(&mut Self, Option<(std::time::Instant, Self::Event)>) -> bool,
     {
-        task::block_on(async move {
-            let mut buf = [0u8; 1024];
-            loop {
-                match self.0.read(&mut buf).await {
-                    Ok(0) | Err(_) => return false,
-                    Ok(n) => {
-                        // 如果回调指示不要继续阻塞，立即退出
-                        if !f(self, Some((Instant::now(), buf[..n].to_vec()))) {
-                            return true;
-                        }
-                    }
-                }
-            }
-        })
+        self.run(f)
     }
 }
 
@@ -128,7 +183,7 @@ mod t {
         driver::SupervisorForSingle::<QXWZService<AuthFile>>::default().join(|e| {
             use driver::SupervisorEventForSingle::*;
             match e {
-                Connected(key, _) => println!("key = {}", key),
+                Connected(key, _) => println!("key = {:?}", key),
                 Event(_, _) => println!("1"),
                 Disconnected => println!("2"),
                 ConnectFailed => println!("3"),
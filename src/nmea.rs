@@ -30,6 +30,54 @@ macro_rules! check {
     }};
 }
 
+macro_rules! field {
+    ($name:expr; $body:ident) => {
+        if let Some(word) = $body.next() {
+            if let Ok(val) = word.parse() {
+                val
+            } else {
+                return Err(FailToParse($name));
+            }
+        } else {
+            return Err(LackOfField($name));
+        }
+    };
+    ($name:expr; $body:ident, $parse:expr) => {
+        if let Some(word) = $body.next() {
+            if let Some(val) = $parse(word) {
+                val
+            } else {
+                return Err(FailToParse($name));
+            }
+        } else {
+            return Err(LackOfField($name));
+        }
+    };
+    // 可以为空的字段
+    ($name:expr; $body:ident?) => {
+        match $body.next() {
+            Some("") => None,
+            Some(word) => match word.parse() {
+                Ok(val) => Some(val),
+                Err(_) => return Err(FailToParse($name)),
+            },
+            None => return Err(LackOfField($name)),
+        }
+    };
+    ($name:expr; $body:ident?, $parse:expr) => {
+        match $body.next() {
+            Some("") => None,
+            Some(word) => match $parse(word) {
+                Some(val) => Some(val),
+                None => return Err(FailToParse($name)),
+            },
+            None => return Err(LackOfField($name)),
+        }
+    };
+}
+
+pub(crate) use field;
+
 impl<const LEN: usize> Buffer<LEN> {
     #[inline]
     pub const fn new() -> Self {
@@ -132,8 +180,22 @@ impl<const LEN: usize> Buffer<LEN> {
     }
 }
 
+/// 去掉语句末尾的校验和
+#[inline]
+pub(crate) fn strip_checksum(s: &str) -> &str {
+    s.trim_end().rsplit_once('*').map_or(s, |(body, _)| body)
+}
+
+/// 度分格式转十进制度
+pub(crate) fn parse_degree(word: &str) -> Option<f64> {
+    let split = word.find('.')?.checked_sub(2)?;
+    let degrees = word.get(..split)?.parse::<f64>().ok()?;
+    let minutes = word.get(split..)?.parse::<f64>().ok()?;
+    Some(degrees + minutes / 60.0)
+}
+
 #[inline]
-fn xor(buf: &[u8]) -> u8 {
+pub(crate) fn xor(buf: &[u8]) -> u8 {
     buf.iter().fold(0, |sum, it| sum ^ it)
 }
 
@@ -0,0 +1,331 @@
+const CRC32: [u32; 256] = crc32_table();
+
+pub(crate) const SYNC: [u8; 3] = [0xaa, 0x44, 0x12];
+
+/// 长头长度，短头的帧不解析
+pub(crate) const HEADER_LEN: usize = 28;
+
+/// NovAtel OEM 二进制日志（长头），兼容此格式的 Unicore 板卡 `LOG xxxB` 输出亦可解析
+#[derive(Clone, Debug)]
+pub struct BinaryLog {
+    /// GPS 周
+    pub week: u16,
+    /// GPS 周内时（毫秒）
+    pub milliseconds: u32,
+    pub body: LogBody,
+}
+
+/// 日志内容
+#[derive(Clone, Debug)]
+pub enum LogBody {
+    /// BESTPOS（42）
+    BestPos(BestPos),
+    /// PSRVEL（100）
+    PsrVel(PsrVel),
+    /// HEADING（971）
+    Heading(HeadingLog),
+    /// 未支持的日志，保留日志号
+    Other(u16),
+}
+
+/// 解算类型
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PositionType {
+    None,
+    FixedPos,
+    FixedHeight,
+    DopplerVelocity,
+    Single,
+    PsrDiff,
+    Waas,
+    Propagated,
+    L1Float,
+    IonoFreeFloat,
+    NarrowFloat,
+    L1Int,
+    WideInt,
+    NarrowInt,
+    PppConverging,
+    Ppp,
+    Other(u32),
+}
+
+/// 最优位置
+#[derive(Clone, Debug)]
+pub struct BestPos {
+    /// 0 表示已解算
+    pub solution_status: u32,
+    pub position_type: PositionType,
+    /// 度
+    pub latitude: f64,
+    pub longitude: f64,
+    /// 海拔高（米）
+    pub height: f64,
+    /// 高程异常（米）
+    pub undulation: f32,
+    /// 纬度、经度和高程的标准差（米）
+    pub latitude_std: f32,
+    pub longitude_std: f32,
+    pub height_std: f32,
+    pub station_id: String,
+    /// 差分龄期（秒）
+    pub differential_age: f32,
+    pub solution_age: f32,
+    /// 跟踪的卫星数
+    pub satellite: u8,
+    /// 参与解算的卫星数
+    pub solution_satellite: u8,
+}
+
+/// 伪距速度
+#[derive(Clone, Debug)]
+pub struct PsrVel {
+    pub solution_status: u32,
+    pub velocity_type: PositionType,
+    /// 测量延迟（秒）
+    pub latency: f32,
+    pub differential_age: f32,
+    /// 水平速度（米每秒）
+    pub horizontal_speed: f64,
+    /// 对地航向（度）
+    pub track: f64,
+    /// 垂直速度（米每秒），向上为正
+    pub vertical_speed: f64,
+}
+
+/// 双天线定向
+#[derive(Clone, Debug)]
+pub struct HeadingLog {
+    pub solution_status: u32,
+    pub position_type: PositionType,
+    /// 基线长度（米）
+    pub length: f32,
+    /// 主天线指向从天线的航向（度）
+    pub heading: f32,
+    /// 俯仰角（度）
+    pub pitch: f32,
+    /// 航向和俯仰的标准差（度）
+    pub heading_std: f32,
+    pub pitch_std: f32,
+    pub station_id: String,
+    pub satellite: u8,
+    pub solution_satellite: u8,
+}
+
+impl From<u32> for PositionType {
+    fn from(value: u32) -> Self {
+        match value {
+            0 => Self::None,
+            1 => Self::FixedPos,
+            2 => Self::FixedHeight,
+            8 => Self::DopplerVelocity,
+            16 => Self::Single,
+            17 => Self::PsrDiff,
+            18 => Self::Waas,
+            19 => Self::Propagated,
+            32 => Self::L1Float,
+            33 => Self::IonoFreeFloat,
+            34 => Self::NarrowFloat,
+            48 => Self::L1Int,
+            49 => Self::WideInt,
+            50 => Self::NarrowInt,
+            68 => Self::PppConverging,
+            69 => Self::Ppp,
+            _ => Self::Other(value),
+        }
+    }
+}
+
+impl PositionType {
+    /// RTK 固定解
+    #[inline]
+    pub fn is_fixed(&self) -> bool {
+        matches!(self, Self::L1Int | Self::WideInt | Self::NarrowInt)
+    }
+
+    /// RTK 浮点解
+    #[inline]
+    pub fn is_float(&self) -> bool {
+        matches!(
+            self,
+            Self::L1Float | Self::IonoFreeFloat | Self::NarrowFloat
+        )
+    }
+}
+
+impl BinaryLog {
+    /// 从完整的帧中解析日志，帧已通过校验
+    pub fn decode(frame: &[u8]) -> Option<Self> {
+        let header = *frame.get(3)? as usize;
+        if header < HEADER_LEN {
+            return None;
+        }
+        let h = Body(frame.get(..header)?);
+        let b = Body(frame.get(header..frame.len().checked_sub(4)?)?);
+        let id = h.u2(4);
+        let body = match id {
+            42 if b.0.len() >= 66 => LogBody::BestPos(BestPos {
+                solution_status: b.u4(0),
+                position_type: b.u4(4).into(),
+                latitude: b.f8(8),
+                longitude: b.f8(16),
+                height: b.f8(24),
+                undulation: b.f4(32),
+                latitude_std: b.f4(40),
+                longitude_std: b.f4(44),
+                height_std: b.f4(48),
+                station_id: b.text(52),
+                differential_age: b.f4(56),
+                solution_age: b.f4(60),
+                satellite: b.0[64],
+                solution_satellite: b.0[65],
+            }),
+            100 if b.0.len() >= 40 => LogBody::PsrVel(PsrVel {
+                solution_status: b.u4(0),
+                velocity_type: b.u4(4).into(),
+                latency: b.f4(8),
+                differential_age: b.f4(12),
+                horizontal_speed: b.f8(16),
+                track: b.f8(24),
+                vertical_speed: b.f8(32),
+            }),
+            971 if b.0.len() >= 38 => LogBody::Heading(HeadingLog {
+                solution_status: b.u4(0),
+                position_type: b.u4(4).into(),
+                length: b.f4(8),
+                heading: b.f4(12),
+                pitch: b.f4(16),
+                heading_std: b.f4(24),
+                pitch_std: b.f4(28),
+                station_id: b.text(32),
+                satellite: b.0[36],
+                solution_satellite: b.0[37],
+            }),
+            42 | 100 | 971 => return None,
+            _ => LogBody::Other(id),
+        };
+        Some(Self {
+            week: h.u2(14),
+            milliseconds: h.u4(16),
+            body,
+        })
+    }
+}
+
+/// 小端读取
+struct Body<'a>(&'a [u8]);
+
+impl Body<'_> {
+    #[inline]
+    fn u2(&self, i: usize) -> u16 {
+        u16::from_le_bytes([self.0[i], self.0[i + 1]])
+    }
+
+    #[inline]
+    fn u4(&self, i: usize) -> u32 {
+        u32::from_le_bytes(self.0[i..i + 4].try_into().unwrap())
+    }
+
+    #[inline]
+    fn f4(&self, i: usize) -> f32 {
+        f32::from_le_bytes(self.0[i..i + 4].try_into().unwrap())
+    }
+
+    #[inline]
+    fn f8(&self, i: usize) -> f64 {
+        f64::from_le_bytes(self.0[i..i + 8].try_into().unwrap())
+    }
+
+    /// 以 0 结尾的 4 字节字符串
+    fn text(&self, i: usize) -> String {
+        let bytes = &self.0[i..i + 4];
+        let end = bytes.iter().position(|b| *b == 0).unwrap_or(4);
+        String::from_utf8_lossy(&bytes[..end]).into_owned()
+    }
+}
+
+/// 计算 NovAtel CRC-32
+pub(crate) fn crc32(buf: &[u8]) -> u32 {
+    buf.iter()
+        .fold(0, |crc, b| (crc >> 8) ^ CRC32[(crc as u8 ^ b) as usize])
+}
+
+const fn crc32_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut j = 0;
+        while j < 8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xedb88320
+            } else {
+                crc >> 1
+            };
+            j += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+#[cfg(test)]
+mod t {
+    use super::*;
+
+    fn frame(id: u16, body: &[u8]) -> Vec<u8> {
+        let mut frame = vec![0u8; 28];
+        frame[..3].copy_from_slice(&SYNC);
+        frame[3] = 28;
+        frame[4..6].copy_from_slice(&id.to_le_bytes());
+        frame[8..10].copy_from_slice(&(body.len() as u16).to_le_bytes());
+        frame[14..16].copy_from_slice(&2200u16.to_le_bytes());
+        frame[16..20].copy_from_slice(&345_600_000u32.to_le_bytes());
+        frame.extend_from_slice(body);
+        let crc = crc32(&frame);
+        frame.extend_from_slice(&crc.to_le_bytes());
+        frame
+    }
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(0x2dfd2d88, crc32(b"123456789"));
+    }
+
+    #[test]
+    fn test_decode() {
+        let mut body = vec![0u8; 44];
+        body[4..8].copy_from_slice(&50u32.to_le_bytes());
+        body[8..12].copy_from_slice(&0.8f32.to_le_bytes());
+        body[12..16].copy_from_slice(&270.5f32.to_le_bytes());
+        body[24..28].copy_from_slice(&0.2f32.to_le_bytes());
+        body[32..35].copy_from_slice(b"999");
+        body[36] = 24;
+        let log = BinaryLog::decode(&frame(971, &body)).unwrap();
+        assert_eq!(2200, log.week);
+        assert_eq!(345_600_000, log.milliseconds);
+        match log.body {
+            LogBody::Heading(heading) => {
+                assert!(heading.position_type.is_fixed());
+                assert_eq!(270.5, heading.heading);
+                assert_eq!(0.8, heading.length);
+                assert_eq!("999", heading.station_id);
+                assert_eq!(24, heading.satellite);
+            }
+            _ => panic!(),
+        }
+
+        assert!(BinaryLog::decode(&frame(42, &body)).is_none());
+        assert!(matches!(
+            BinaryLog::decode(&frame(1, &[])).unwrap().body,
+            LogBody::Other(1)
+        ));
+
+        // 短头的帧不含周和周内时
+        let mut short = vec![0xaa, 0x44, 0x12, 12, 42, 0, 0, 0, 0, 0, 0, 0];
+        let crc = crc32(&short);
+        short.extend_from_slice(&crc.to_le_bytes());
+        assert!(BinaryLog::decode(&short).is_none());
+    }
+}
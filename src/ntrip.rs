@@ -0,0 +1,424 @@
+use crate::sourcetable::SourceTable;
+use async_std::{
+    future,
+    io::{self, prelude::BufReadExt, BufReader, ReadExt, WriteExt},
+    net::TcpStream,
+};
+use socket2::{SockRef, TcpKeepalive};
+use std::{fmt, time::Duration};
+
+const USER_AGENT: &str = concat!("NTRIP rtk-qxwz/", env!("CARGO_PKG_VERSION"));
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+/// 连接空闲多久后开始发送保活探测
+const KEEPALIVE_TIME: Duration = Duration::from_secs(10);
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// NTRIP 协议版本
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum NtripVersion {
+    /// NTRIP 1.0，回复 `ICY 200 OK`
+    V1,
+    /// NTRIP 2.0，HTTP/1.1，可能使用分块传输
+    V2,
+}
+
+/// CORS 服务及挂载点
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct NtripCaster {
+    pub host: String,
+    pub port: u16,
+    pub mountpoint: String,
+    pub version: NtripVersion,
+    /// base64 编码的 `user:password`
+    pub auth: Option<String>,
+}
+
+/// NTRIP 握手失败的原因
+#[derive(Debug)]
+pub enum NtripError {
+    /// 连接或读写失败
+    Io(io::Error),
+    /// 握手超时
+    Timeout,
+    /// 账号或密码错误
+    Unauthorized,
+    /// 挂载点不存在
+    NotFound,
+    /// 请求数据流时服务器回复了源列表，通常说明挂载点不存在
+    SourceTable,
+    /// 其他状态行
+    Status(String),
+}
+
+/// 已完成握手的 NTRIP 数据流
+pub(crate) struct NtripStream {
+    reader: BufReader<TcpStream>,
+    chunked: Option<Chunked>,
+}
+
+/// 服务器回复的内容
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Reply {
+    Stream,
+    SourceTable,
+}
+
+/// 分块传输解码状态
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Chunked {
+    Size(usize),
+    Ext(usize),
+    Data(usize),
+    DataEnd,
+    End,
+}
+
+impl NtripCaster {
+    /// 千寻位置服务
+    pub fn qxwz(auth: String) -> Self {
+        Self {
+            host: "203.107.45.154".into(),
+            port: 8002,
+            mountpoint: "AUTO".into(),
+            version: NtripVersion::V1,
+            auth: Some(auth),
+        }
+    }
+
+    /// 不需要登录的 NTRIP 1.0 服务
+    pub fn new(host: impl Into<String>, port: u16, mountpoint: impl Into<String>) -> Self {
+        Self {
+            host: host.into(),
+            port,
+            mountpoint: mountpoint.into(),
+            version: NtripVersion::V1,
+            auth: None,
+        }
+    }
+
+    /// 登录的用户名，用于报告正在使用的账号
+    pub fn user(&self) -> Option<String> {
+        let auth = base64::decode(self.auth.as_ref()?).ok()?;
+        let auth = String::from_utf8(auth).ok()?;
+        Some(
+            auth.split_once(':')
+                .map_or(auth.as_str(), |(user, _)| user)
+                .into(),
+        )
+    }
+
+    /// 设置用户名和密码
+    pub fn with_login(mut self, user: &str, password: &str) -> Self {
+        self.auth = Some(base64::encode(format!("{}:{}", user, password)));
+        self
+    }
+
+    fn request(&self) -> String {
+        let mut request = match self.version {
+            NtripVersion::V1 => format!(
+                "\
+GET /{} HTTP/1.0\r\n\
+User-Agent: {}\r\n\
+Accept: */*\r\n",
+                self.mountpoint, USER_AGENT
+            ),
+            NtripVersion::V2 => format!(
+                "\
+GET /{} HTTP/1.1\r\n\
+Host: {}:{}\r\n\
+Ntrip-Version: Ntrip/2.0\r\n\
+User-Agent: {}\r\n\
+Connection: close\r\n",
+                self.mountpoint, self.host, self.port, USER_AGENT
+            ),
+        };
+        if let Some(ref auth) = self.auth {
+            request += &format!("Authorization: Basic {}\r\n", auth);
+        }
+        request += "\r\n";
+        request
+    }
+}
+
+impl fmt::Debug for NtripCaster {
+    /// 只输出用户名，避免日志泄露密码
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NtripCaster")
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("mountpoint", &self.mountpoint)
+            .field("version", &self.version)
+            .field("user", &self.user())
+            .finish()
+    }
+}
+
+impl NtripCaster {
+    /// 下载并解析服务器的源列表
+    ///
+    /// 握手和每次读取都有超时，与连接数据流时相同。
+    pub async fn sourcetable(&self) -> Result<SourceTable, NtripError> {
+        let caster = Self {
+            mountpoint: String::new(),
+            ..self.clone()
+        };
+        let mut stream = match NtripStream::open(&caster).await? {
+            (Reply::SourceTable, stream) => stream,
+            (Reply::Stream, _) => return Err(NtripError::Status("not a sourcetable".into())),
+        };
+        let mut text = Vec::new();
+        let mut buf = [0u8; 1024];
+        loop {
+            let n = match future::timeout(HANDSHAKE_TIMEOUT, stream.read(&mut buf)).await {
+                Ok(Some(n)) => n,
+                Ok(None) => break,
+                Err(_) => return Err(NtripError::Timeout),
+            };
+            text.extend_from_slice(&buf[..n]);
+            if text.ends_with(b"ENDSOURCETABLE\r\n") {
+                break;
+            }
+        }
+        String::from_utf8_lossy(&text)
+            .parse()
+            .map_err(|_| NtripError::Io(io::ErrorKind::InvalidData.into()))
+    }
+}
+
+impl NtripError {
+    fn from_status(line: &str) -> Self {
+        let code = line
+            .strip_prefix("HTTP/")
+            .and_then(|s| s.split_whitespace().nth(1));
+        match code {
+            Some("401") => Self::Unauthorized,
+            Some("404") => Self::NotFound,
+            // NTRIP 1.0 服务器常见的错误回复
+            _ if line.contains("Bad Password") => Self::Unauthorized,
+            _ if line.contains("Bad Mountpoint") => Self::NotFound,
+            _ => Self::Status(line.into()),
+        }
+    }
+}
+
+impl From<io::Error> for NtripError {
+    #[inline]
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl NtripStream {
+    pub async fn connect(caster: &NtripCaster) -> Result<Self, NtripError> {
+        match NtripStream::open(caster).await? {
+            (Reply::Stream, stream) => Ok(stream),
+            (Reply::SourceTable, _) => Err(NtripError::SourceTable),
+        }
+    }
+
+    async fn open(caster: &NtripCaster) -> Result<(Reply, Self), NtripError> {
+        future::timeout(HANDSHAKE_TIMEOUT, Self::handshake(caster))
+            .await
+            .unwrap_or(Err(NtripError::Timeout))
+    }
+
+    async fn handshake(caster: &NtripCaster) -> Result<(Reply, Self), NtripError> {
+        let mut tcp = connect_tcp(caster).await?;
+        tcp.write_all(caster.request().as_bytes()).await?;
+
+        let mut reader = BufReader::new(tcp);
+        let mut line = String::new();
+        if reader.read_line(&mut line).await? == 0 {
+            return Err(NtripError::Io(io::ErrorKind::UnexpectedEof.into()));
+        }
+        let mut reply = match line.trim() {
+            "ICY 200 OK" => {
+                return Ok((
+                    Reply::Stream,
+                    Self {
+                        reader,
+                        chunked: None,
+                    },
+                ))
+            }
+            "SOURCETABLE 200 OK" => Reply::SourceTable,
+            "HTTP/1.0 200 OK" | "HTTP/1.1 200 OK" => Reply::Stream,
+            status => return Err(NtripError::from_status(status)),
+        };
+        // 读取响应头直到空行
+        let mut chunked = false;
+        loop {
+            line.clear();
+            if reader.read_line(&mut line).await? == 0 {
+                return Err(NtripError::Io(io::ErrorKind::UnexpectedEof.into()));
+            }
+            let header = line.trim();
+            if header.is_empty() {
+                break;
+            }
+            if let Some((key, value)) = header.split_once(':') {
+                let key = key.trim();
+                let value = value.trim();
+                if key.eq_ignore_ascii_case("Transfer-Encoding") {
+                    chunked = value.eq_ignore_ascii_case("chunked");
+                } else if key.eq_ignore_ascii_case("Content-Type")
+                    && value.eq_ignore_ascii_case("gnss/sourcetable")
+                {
+                    reply = Reply::SourceTable;
+                }
+            }
+        }
+        Ok((
+            reply,
+            Self {
+                reader,
+                chunked: if chunked {
+                    Some(Chunked::Size(0))
+                } else {
+                    None
+                },
+            },
+        ))
+    }
+
+    /// 用于上传 GGA 的写端
+    #[inline]
+    pub fn writer(&self) -> TcpStream {
+        self.reader.get_ref().clone()
+    }
+
+    /// 读取差分数据，连接断开或格式错误返回 `None`
+    pub async fn read(&mut self, buf: &mut [u8]) -> Option<usize> {
+        loop {
+            let n = self.reader.read(buf).await.ok().filter(|n| *n > 0)?;
+            match self.chunked {
+                Some(ref mut chunked) => {
+                    let n = chunked.decode(&mut buf[..n])?;
+                    if n > 0 {
+                        return Some(n);
+                    }
+                    if *chunked == Chunked::End {
+                        return None;
+                    }
+                }
+                None => return Some(n),
+            }
+        }
+    }
+}
+
+impl Chunked {
+    /// 原地解码，返回有效数据长度
+    fn decode(&mut self, buf: &mut [u8]) -> Option<usize> {
+        use Chunked::*;
+        let mut n = 0;
+        for i in 0..buf.len() {
+            let byte = buf[i];
+            *self = match *self {
+                Size(len) => match byte {
+                    b';' | b'\r' => Ext(len),
+                    b'\n' if len == 0 => End,
+                    b'\n' => Data(len),
+                    _ => Size(len.checked_mul(16)? + (byte as char).to_digit(16)? as usize),
+                },
+                Ext(len) => match byte {
+                    b'\n' if len == 0 => End,
+                    b'\n' => Data(len),
+                    _ => Ext(len),
+                },
+                Data(len) => {
+                    buf[n] = byte;
+                    n += 1;
+                    if len > 1 {
+                        Data(len - 1)
+                    } else {
+                        DataEnd
+                    }
+                }
+                DataEnd => match byte {
+                    b'\r' => DataEnd,
+                    b'\n' => Size(0),
+                    _ => return None,
+                },
+                End => End,
+            };
+        }
+        Some(n)
+    }
+}
+
+/// 建立开启了保活的 TCP 连接
+///
+/// 4G 模块上常见半开连接，依靠保活探测尽早发现。连接超时由握手的超时控制。
+async fn connect_tcp(caster: &NtripCaster) -> io::Result<TcpStream> {
+    let tcp = TcpStream::connect((caster.host.as_str(), caster.port)).await?;
+    SockRef::from(&tcp).set_tcp_keepalive(
+        &TcpKeepalive::new()
+            .with_time(KEEPALIVE_TIME)
+            .with_interval(KEEPALIVE_INTERVAL),
+    )?;
+    Ok(tcp)
+}
+
+#[cfg(test)]
+mod t {
+    use super::*;
+
+    #[test]
+    fn test_request() {
+        let mut caster = NtripCaster::qxwz("dXNlcjpwYXNz".into());
+        assert!(caster.request().starts_with("GET /AUTO HTTP/1.0\r\n"));
+        assert!(caster
+            .request()
+            .ends_with("Authorization: Basic dXNlcjpwYXNz\r\n\r\n"));
+
+        assert_eq!(Some("user".into()), caster.user());
+        assert!(!format!("{:?}", caster).contains("dXNlcjpwYXNz"));
+
+        caster.version = NtripVersion::V2;
+        caster.auth = None;
+        let request = caster.request();
+        assert!(request.starts_with("GET /AUTO HTTP/1.1\r\nHost: 203.107.45.154:8002\r\n"));
+        assert!(request.contains("Ntrip-Version: Ntrip/2.0\r\n"));
+        assert!(!request.contains("Authorization"));
+    }
+
+    #[test]
+    fn test_status() {
+        use NtripError::*;
+        assert!(matches!(
+            NtripError::from_status("HTTP/1.1 401 Unauthorized"),
+            Unauthorized
+        ));
+        assert!(matches!(
+            NtripError::from_status("HTTP/1.1 404 Not Found"),
+            NotFound
+        ));
+        assert!(matches!(
+            NtripError::from_status("ERROR - Bad Password"),
+            Unauthorized
+        ));
+        assert!(matches!(
+            NtripError::from_status("HTTP/1.1 503 Service Unavailable"),
+            Status(_)
+        ));
+    }
+
+    #[test]
+    fn test_chunked() {
+        let mut chunked = Chunked::Size(0);
+        let mut buf = *b"4\r\nabcd\r\n3;ext\r\nef";
+        let n = chunked.decode(&mut buf).unwrap();
+        assert_eq!(b"abcdef", &buf[..n]);
+        assert_eq!(Chunked::Data(1), chunked);
+
+        let mut buf = *b"g\r\nA\r\n0123456789\r\n0\r\n\r\n";
+        let n = chunked.decode(&mut buf).unwrap();
+        assert_eq!(b"g0123456789", &buf[..n]);
+        assert_eq!(Chunked::End, chunked);
+
+        let mut chunked = Chunked::Size(0);
+        let mut buf = *b"xyz\r\n";
+        assert_eq!(None, chunked.decode(&mut buf));
+    }
+}
@@ -0,0 +1,106 @@
+use serial_port::SerialId;
+
+/// 串口筛选条件
+#[derive(Clone, Copy, Debug)]
+pub enum PortFilter {
+    /// USB 厂商号和产品号
+    Usb { vid: u16, pid: u16 },
+    /// USB 序列号
+    Serial(&'static str),
+    /// 串口名包含指定字符串
+    Path(&'static str),
+}
+
+/// 串口的 USB 信息
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+pub struct UsbInfo {
+    pub vid: u16,
+    pub pid: u16,
+    pub serial: Option<String>,
+}
+
+impl PortFilter {
+    pub fn matches(&self, id: &SerialId) -> bool {
+        match self {
+            Self::Usb { vid, pid } => {
+                matches!(UsbInfo::of(id), Some(info) if info.vid == *vid && info.pid == *pid)
+            }
+            Self::Serial(serial) => {
+                matches!(UsbInfo::of(id), Some(UsbInfo { serial: Some(s), .. }) if s == *serial)
+            }
+            Self::Path(pattern) => id.key.to_string().contains(pattern),
+        }
+    }
+}
+
+impl UsbInfo {
+    /// 查询串口的 USB 信息，非 USB 串口返回 `None`
+    pub fn of(id: &SerialId) -> Option<Self> {
+        #[cfg(target_os = "linux")]
+        if let Some(info) = Self::from_sysfs(&id.key.to_string()) {
+            return Some(info);
+        }
+        Self::from_comment(&id.comment)
+    }
+
+    /// 从 sysfs 中 tty 设备的上级 USB 设备读取
+    #[cfg(target_os = "linux")]
+    fn from_sysfs(path: &str) -> Option<Self> {
+        use std::{fs, path::Path};
+
+        let name = path.rsplit('/').next()?;
+        let device =
+            fs::canonicalize(Path::new("/sys/class/tty").join(name).join("device")).ok()?;
+        let usb = device.ancestors().find(|p| p.join("idVendor").is_file())?;
+        let read = |file: &str| {
+            fs::read_to_string(usb.join(file))
+                .ok()
+                .map(|s| s.trim().to_string())
+        };
+        Some(Self {
+            vid: u16::from_str_radix(&read("idVendor")?, 16).ok()?,
+            pid: u16::from_str_radix(&read("idProduct")?, 16).ok()?,
+            serial: read("serial"),
+        })
+    }
+
+    /// 从形如 `USB\VID_1A86&PID_7523\5&2F2A0F3&0&1` 的硬件描述中解析
+    fn from_comment(comment: &str) -> Option<Self> {
+        let upper = comment.to_ascii_uppercase();
+        let hex = |tag: &str| {
+            let i = upper.find(tag)? + tag.len();
+            u16::from_str_radix(upper.get(i..i + 4)?, 16).ok()
+        };
+        let vid = hex("VID_")?;
+        let pid = hex("PID_")?;
+        let i = upper.find("PID_")? + 8;
+        let serial = comment
+            .get(i..)
+            .and_then(|s| s.strip_prefix('\\'))
+            .and_then(|s| s.split(|c: char| c.is_whitespace() || c == ')').next())
+            .filter(|s| !s.is_empty() && !s.contains('&'))
+            .map(|s| s.to_string());
+        Some(Self { vid, pid, serial })
+    }
+}
+
+#[test]
+fn test_from_comment() {
+    assert_eq!(
+        Some(UsbInfo {
+            vid: 0x1a86,
+            pid: 0x7523,
+            serial: None
+        }),
+        UsbInfo::from_comment(r"USB\VID_1A86&PID_7523\5&2F2A0F3&0&1")
+    );
+    assert_eq!(
+        Some(UsbInfo {
+            vid: 0x1546,
+            pid: 0x01a9,
+            serial: Some("A12345".into())
+        }),
+        UsbInfo::from_comment(r"u-blox GNSS receiver (USB\VID_1546&PID_01A9\A12345)")
+    );
+    assert_eq!(None, UsbInfo::from_comment("Communications Port"));
+}
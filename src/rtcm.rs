@@ -0,0 +1,526 @@
+const PREAMBLE: u8 = 0xd3;
+const CRC24Q: [u32; 256] = crc24q_table();
+
+/// RTCM 3 帧解析缓冲区
+pub(crate) struct Buffer<const LEN: usize> {
+    buf: [u8; LEN],
+    p_read: usize,
+    p_write: usize,
+    stats: RtcmStats,
+}
+
+/// RTCM 3 帧统计
+#[derive(Clone, Copy, Default, Debug)]
+pub struct RtcmStats {
+    /// 校验通过的帧数
+    pub frames: usize,
+    /// 校验失败的帧数
+    pub bad_frames: usize,
+    /// 未能组成帧而丢弃的字节数
+    pub dropped_bytes: usize,
+}
+
+impl<const LEN: usize> Buffer<LEN> {
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            buf: [0u8; LEN],
+            p_read: 0,
+            p_write: 0,
+            stats: RtcmStats {
+                frames: 0,
+                bad_frames: 0,
+                dropped_bytes: 0,
+            },
+        }
+    }
+
+    #[inline]
+    pub fn stats(&self) -> RtcmStats {
+        self.stats
+    }
+
+    pub fn write_buf(&mut self) -> &mut [u8] {
+        if self.p_read > 0 {
+            self.buf.copy_within(self.p_read..self.p_write, 0);
+            self.p_write -= self.p_read;
+            self.p_read = 0;
+        }
+        // 缓冲区已满仍不能组成帧，丢弃最早的字节
+        if self.p_write == LEN {
+            self.drop(1);
+            return self.write_buf();
+        }
+        &mut self.buf[self.p_write..]
+    }
+
+    #[inline]
+    pub fn extend(&mut self, n: usize) {
+        self.p_write += n;
+    }
+
+    /// 解析一个完整的帧，包括帧头和校验位
+    pub fn parse(&mut self) -> Option<&[u8]> {
+        loop {
+            // 找到帧头
+            match self.buf[self.p_read..self.p_write]
+                .iter()
+                .position(|b| *b == PREAMBLE)
+            {
+                Some(i) => self.drop(i),
+                None => {
+                    self.drop(self.p_write - self.p_read);
+                    return None;
+                }
+            }
+            let frame = &self.buf[self.p_read..self.p_write];
+            if frame.len() < 3 {
+                return None;
+            }
+            // 保留位必须为 0
+            if frame[1] & 0xfc != 0 {
+                self.drop(1);
+                continue;
+            }
+            let len = 3 + (((frame[1] as usize) << 8) | frame[2] as usize) + 3;
+            if frame.len() < len {
+                return None;
+            }
+            let (body, crc) = frame[..len].split_at(len - 3);
+            if crc24q(body) == ((crc[0] as u32) << 16 | (crc[1] as u32) << 8 | crc[2] as u32) {
+                self.stats.frames += 1;
+                let begin = self.p_read;
+                self.p_read += len;
+                return Some(&self.buf[begin..][..len]);
+            }
+            self.stats.bad_frames += 1;
+            self.drop(1);
+        }
+    }
+
+    #[inline]
+    fn drop(&mut self, n: usize) {
+        self.p_read += n;
+        self.stats.dropped_bytes += n;
+    }
+}
+
+/// RTCM 3 电文
+#[derive(Clone, Debug)]
+pub enum RtcmMessage {
+    /// 1005/1006
+    StationArp(StationArp),
+    /// 1007/1008/1033
+    AntennaDescriptor(AntennaDescriptor),
+    /// 1230
+    GlonassBiases(GlonassBiases),
+    /// MSM 观测值，仅解析电文头
+    Msm(MsmHeader),
+    /// 未支持的电文，保留电文号
+    Other(u16),
+}
+
+/// 参考站天线参考点
+#[derive(Clone, Default, Debug)]
+pub struct StationArp {
+    pub station_id: u16,
+    pub itrf_year: u8,
+    pub gps: bool,
+    pub glonass: bool,
+    pub galileo: bool,
+    /// 是否虚拟参考站
+    pub virtual_station: bool,
+    /// ECEF 坐标（米）
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    /// 天线高（米），仅 1006 提供
+    pub antenna_height: Option<f64>,
+}
+
+/// 天线和接收机描述
+#[derive(Clone, Default, Debug)]
+pub struct AntennaDescriptor {
+    pub station_id: u16,
+    pub descriptor: String,
+    pub setup_id: u8,
+    /// 天线序列号，1008/1033 提供
+    pub serial: Option<String>,
+    /// 接收机型号、固件版本和序列号，仅 1033 提供
+    pub receiver: Option<String>,
+    pub firmware: Option<String>,
+    pub receiver_serial: Option<String>,
+}
+
+/// GLONASS 码相位偏差（米）
+#[derive(Clone, Default, Debug)]
+pub struct GlonassBiases {
+    pub station_id: u16,
+    /// 是否已对齐
+    pub aligned: bool,
+    pub l1_ca: Option<f64>,
+    pub l1_p: Option<f64>,
+    pub l2_ca: Option<f64>,
+    pub l2_p: Option<f64>,
+}
+
+/// 卫星系统
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Constellation {
+    Gps,
+    Glonass,
+    Galileo,
+    Sbas,
+    Qzss,
+    BeiDou,
+}
+
+/// MSM 电文头
+#[derive(Clone, Debug)]
+pub struct MsmHeader {
+    pub constellation: Constellation,
+    /// MSM 类型，1 ~ 7
+    pub msm: u8,
+    pub station_id: u16,
+    /// 历元时间（毫秒），GLONASS 高 3 位为星期
+    pub epoch: u32,
+    /// 是否还有同历元的后续电文
+    pub multiple: bool,
+    pub iods: u8,
+    pub clock_steering: u8,
+    pub external_clock: u8,
+    pub smoothing: bool,
+    pub smoothing_interval: u8,
+    /// 最高位为 1 号卫星
+    pub satellite_mask: u64,
+    /// 最高位为 1 号信号
+    pub signal_mask: u32,
+    /// 每颗卫星依次占 `signals().count()` 位，靠低位对齐
+    pub cell_mask: u64,
+}
+
+/// 按位读取电文
+struct Bits<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl RtcmMessage {
+    /// 从完整的帧中取出电文号
+    pub fn number(frame: &[u8]) -> Option<u16> {
+        Bits::new(payload(frame)?).u(12).map(|n| n as u16)
+    }
+
+    /// 从完整的帧中解析电文
+    pub fn decode(frame: &[u8]) -> Option<Self> {
+        let mut bits = Bits::new(payload(frame)?);
+        let number = bits.u(12)? as u16;
+        match number {
+            1005 | 1006 => {
+                let mut arp = StationArp {
+                    station_id: bits.u(12)? as u16,
+                    itrf_year: bits.u(6)? as u8,
+                    gps: bits.bool()?,
+                    glonass: bits.bool()?,
+                    galileo: bits.bool()?,
+                    virtual_station: bits.bool()?,
+                    ..Default::default()
+                };
+                arp.x = bits.i(38)? as f64 * 1e-4;
+                bits.skip(2);
+                arp.y = bits.i(38)? as f64 * 1e-4;
+                bits.skip(2);
+                arp.z = bits.i(38)? as f64 * 1e-4;
+                if number == 1006 {
+                    arp.antenna_height = Some(bits.u(16)? as f64 * 1e-4);
+                }
+                Some(Self::StationArp(arp))
+            }
+            1007 | 1008 | 1033 => {
+                let mut antenna = AntennaDescriptor {
+                    station_id: bits.u(12)? as u16,
+                    descriptor: bits.string()?,
+                    setup_id: bits.u(8)? as u8,
+                    ..Default::default()
+                };
+                if number != 1007 {
+                    antenna.serial = Some(bits.string()?);
+                }
+                if number == 1033 {
+                    antenna.receiver = Some(bits.string()?);
+                    antenna.firmware = Some(bits.string()?);
+                    antenna.receiver_serial = Some(bits.string()?);
+                }
+                Some(Self::AntennaDescriptor(antenna))
+            }
+            1230 => {
+                let mut biases = GlonassBiases {
+                    station_id: bits.u(12)? as u16,
+                    aligned: bits.bool()?,
+                    ..Default::default()
+                };
+                bits.skip(3);
+                let mask = bits.u(4)?;
+                for (i, bias) in [
+                    &mut biases.l1_ca,
+                    &mut biases.l1_p,
+                    &mut biases.l2_ca,
+                    &mut biases.l2_p,
+                ]
+                .into_iter()
+                .enumerate()
+                {
+                    if mask & (0b1000 >> i) != 0 {
+                        *bias = Some(bits.i(16)? as f64 * 0.02);
+                    }
+                }
+                Some(Self::GlonassBiases(biases))
+            }
+            1071..=1127 if (1..=7).contains(&(number % 10)) => {
+                let constellation = match number / 10 {
+                    107 => Constellation::Gps,
+                    108 => Constellation::Glonass,
+                    109 => Constellation::Galileo,
+                    110 => Constellation::Sbas,
+                    111 => Constellation::Qzss,
+                    112 => Constellation::BeiDou,
+                    _ => unreachable!(),
+                };
+                let mut header = MsmHeader {
+                    constellation,
+                    msm: (number % 10) as u8,
+                    station_id: bits.u(12)? as u16,
+                    epoch: bits.u(30)? as u32,
+                    multiple: bits.bool()?,
+                    iods: bits.u(3)? as u8,
+                    clock_steering: {
+                        bits.skip(7);
+                        bits.u(2)? as u8
+                    },
+                    external_clock: bits.u(2)? as u8,
+                    smoothing: bits.bool()?,
+                    smoothing_interval: bits.u(3)? as u8,
+                    satellite_mask: bits.u(64)?,
+                    signal_mask: bits.u(32)? as u32,
+                    cell_mask: 0,
+                };
+                let cells = header.satellite_mask.count_ones() * header.signal_mask.count_ones();
+                if cells > 64 {
+                    return None;
+                }
+                header.cell_mask = bits.u(cells as usize)?;
+                Some(Self::Msm(header))
+            }
+            _ => Some(Self::Other(number)),
+        }
+    }
+}
+
+impl MsmHeader {
+    /// 电文中包含的卫星号，从 1 开始
+    pub fn satellites(&self) -> impl Iterator<Item = u8> + '_ {
+        (0..64u8)
+            .filter(move |i| self.satellite_mask & (1 << (63 - i)) != 0)
+            .map(|i| i + 1)
+    }
+
+    /// 电文中包含的信号号，从 1 开始
+    pub fn signals(&self) -> impl Iterator<Item = u8> + '_ {
+        (0..32u8)
+            .filter(move |i| self.signal_mask & (1 << (31 - i)) != 0)
+            .map(|i| i + 1)
+    }
+}
+
+impl<'a> Bits<'a> {
+    #[inline]
+    fn new(buf: &'a [u8]) -> Self {
+        Self { buf, pos: 0 }
+    }
+
+    #[inline]
+    fn skip(&mut self, n: usize) {
+        self.pos += n;
+    }
+
+    fn u(&mut self, n: usize) -> Option<u64> {
+        if self.pos + n > self.buf.len() * 8 {
+            return None;
+        }
+        let mut result = 0u64;
+        for _ in 0..n {
+            let bit = (self.buf[self.pos / 8] >> (7 - self.pos % 8)) & 1;
+            result = (result << 1) | bit as u64;
+            self.pos += 1;
+        }
+        Some(result)
+    }
+
+    fn i(&mut self, n: usize) -> Option<i64> {
+        let shift = 64 - n;
+        Some(((self.u(n)? << shift) as i64) >> shift)
+    }
+
+    #[inline]
+    fn bool(&mut self) -> Option<bool> {
+        self.u(1).map(|b| b != 0)
+    }
+
+    /// 8 位长度加字符
+    fn string(&mut self) -> Option<String> {
+        let len = self.u(8)? as usize;
+        (0..len)
+            .map(|_| self.u(8).map(|c| c as u8 as char))
+            .collect()
+    }
+}
+
+/// 取出帧中的电文
+#[inline]
+fn payload(frame: &[u8]) -> Option<&[u8]> {
+    frame.get(3..frame.len().checked_sub(3)?)
+}
+
+/// 计算 CRC-24Q
+pub(crate) fn crc24q(buf: &[u8]) -> u32 {
+    buf.iter().fold(0, |crc, b| {
+        ((crc << 8) & 0xffffff) ^ CRC24Q[((crc >> 16) as u8 ^ b) as usize]
+    })
+}
+
+const fn crc24q_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = (i as u32) << 16;
+        let mut j = 0;
+        while j < 8 {
+            crc <<= 1;
+            if crc & 0x1000000 != 0 {
+                crc ^= 0x1864cfb;
+            }
+            j += 1;
+        }
+        table[i] = crc & 0xffffff;
+        i += 1;
+    }
+    table
+}
+
+#[cfg(test)]
+mod t {
+    use super::*;
+
+    fn frame(payload: &[u8]) -> Vec<u8> {
+        let mut frame = vec![PREAMBLE, (payload.len() >> 8) as u8, payload.len() as u8];
+        frame.extend_from_slice(payload);
+        let crc = crc24q(&frame);
+        frame.extend_from_slice(&crc.to_be_bytes()[1..]);
+        frame
+    }
+
+    fn push<const LEN: usize>(buffer: &mut Buffer<LEN>, bytes: &[u8]) {
+        buffer.write_buf()[..bytes.len()].copy_from_slice(bytes);
+        buffer.extend(bytes.len());
+    }
+
+    /// 按位写入电文
+    #[derive(Default)]
+    struct Writer(Vec<u8>, usize);
+
+    impl Writer {
+        fn put(&mut self, n: usize, val: i64) -> &mut Self {
+            for i in (0..n).rev() {
+                if self.1 == self.0.len() * 8 {
+                    self.0.push(0);
+                }
+                let bit = ((val >> i) & 1) as u8;
+                *self.0.last_mut().unwrap() |= bit << (7 - self.1 % 8);
+                self.1 += 1;
+            }
+            self
+        }
+    }
+
+    #[test]
+    fn test_crc24q() {
+        assert_eq!(0xcde703, crc24q(b"123456789"));
+    }
+
+    #[test]
+    fn test_parse() {
+        let frame0 = frame(&[0x3e, 0xd0, 0x00, 0x03]);
+        let frame1 = frame(&[0x43, 0x50, 0x00, 0x01, 0x02]);
+        let mut bad = frame(&[0x3e, 0xd0, 0x00, 0x04]);
+        bad[4] ^= 0xff;
+
+        let mut buffer = Buffer::<256>::new();
+        push(&mut buffer, b"garbage");
+        push(&mut buffer, &frame0);
+        push(&mut buffer, &bad);
+        push(&mut buffer, &frame1[..4]);
+        assert_eq!(Some(frame0.as_slice()), buffer.parse());
+        assert_eq!(None, buffer.parse());
+
+        push(&mut buffer, &frame1[4..]);
+        assert_eq!(Some(frame1.as_slice()), buffer.parse());
+        assert_eq!(None, buffer.parse());
+
+        let stats = buffer.stats();
+        assert_eq!(2, stats.frames);
+        assert_eq!(1, stats.bad_frames);
+        assert_eq!(7 + bad.len(), stats.dropped_bytes);
+    }
+
+    #[test]
+    fn test_decode_arp() {
+        let mut w = Writer::default();
+        w.put(12, 1006).put(12, 2003).put(6, 0);
+        w.put(1, 1).put(1, 1).put(1, 0).put(1, 0);
+        w.put(38, -21766010123).put(2, 0);
+        w.put(38, 43952013456).put(2, 0);
+        w.put(38, 40484561789).put(16, 15_000);
+        let frame = frame(&w.0);
+
+        assert_eq!(Some(1006), RtcmMessage::number(&frame));
+        match RtcmMessage::decode(&frame) {
+            Some(RtcmMessage::StationArp(arp)) => {
+                assert_eq!(2003, arp.station_id);
+                assert!(arp.gps && arp.glonass && !arp.galileo);
+                assert!((arp.x + 2176601.0123).abs() < 1e-6);
+                assert!((arp.y - 4395201.3456).abs() < 1e-6);
+                assert!((arp.z - 4048456.1789).abs() < 1e-6);
+                assert_eq!(Some(1.5), arp.antenna_height);
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn test_decode_msm() {
+        let mut w = Writer::default();
+        w.put(12, 1127).put(12, 0).put(30, 123_456_000);
+        w.put(1, 1)
+            .put(3, 0)
+            .put(7, 0)
+            .put(2, 0)
+            .put(2, 0)
+            .put(1, 0)
+            .put(3, 0);
+        w.put(64, 0b101 << 61).put(32, 1 << 30).put(2, 0b11);
+        let frame = frame(&w.0);
+
+        match RtcmMessage::decode(&frame) {
+            Some(RtcmMessage::Msm(msm)) => {
+                assert_eq!(Constellation::BeiDou, msm.constellation);
+                assert_eq!(7, msm.msm);
+                assert_eq!(123_456_000, msm.epoch);
+                assert!(msm.multiple);
+                assert_eq!(vec![1, 3], msm.satellites().collect::<Vec<_>>());
+                assert_eq!(vec![2], msm.signals().collect::<Vec<_>>());
+                assert_eq!(0b11, msm.cell_mask);
+            }
+            _ => panic!(),
+        }
+    }
+}
@@ -0,0 +1,454 @@
+use crate::{
+    nmea::{field, parse_degree, strip_checksum},
+    Gpgga, GpggaParseError, NmeaTime, Talker,
+};
+use std::str::FromStr;
+
+/// 支持解析的 NMEA 语句
+#[derive(Debug)]
+pub enum NmeaSentence {
+    Gga(Gpgga),
+    Rmc(Rmc),
+    Vtg(Vtg),
+    Gsa(Gsa),
+    Gsv(Gsv),
+    Gst(Gst),
+    Zda(Zda),
+    Hdt(Hdt),
+    Gns(Gns),
+}
+
+/// 日期
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct NmeaDate {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+}
+
+/// 推荐最小定位信息
+#[derive(Default, Debug)]
+pub struct Rmc {
+    pub utc: Option<NmeaTime>,
+    /// `A` 有效，`V` 无效
+    pub valid: bool,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    /// 对地速度（节）
+    pub speed: Option<f32>,
+    /// 对地航向（度）
+    pub course: Option<f32>,
+    pub date: Option<NmeaDate>,
+    /// 磁偏角（度），西偏为负
+    pub magnetic_variation: Option<f32>,
+    /// 定位模式，NMEA 2.3 以上提供
+    pub mode: Option<char>,
+}
+
+/// 对地速度和航向
+#[derive(Default, Debug)]
+pub struct Vtg {
+    pub course_true: Option<f32>,
+    pub course_magnetic: Option<f32>,
+    pub speed_knots: Option<f32>,
+    pub speed_kmh: Option<f32>,
+    pub mode: Option<char>,
+}
+
+/// 参与解算的卫星和精度因子
+#[derive(Default, Debug)]
+pub struct Gsa {
+    /// `M` 手动，`A` 自动
+    pub selection: char,
+    /// 1 未定位，2 二维，3 三维
+    pub fix: u8,
+    pub satellites: Vec<u16>,
+    pub pdop: Option<f32>,
+    pub hdop: Option<f32>,
+    pub vdop: Option<f32>,
+    /// 卫星系统，NMEA 4.1 以上提供
+    pub system_id: Option<u8>,
+}
+
+/// 可见卫星
+#[derive(Default, Debug)]
+pub struct Gsv {
+    /// 语句总数
+    pub total: u8,
+    /// 本句序号，从 1 开始
+    pub index: u8,
+    /// 可见卫星总数
+    pub in_view: u16,
+    pub satellites: Vec<SatelliteInfo>,
+    /// 信号号，NMEA 4.1 以上提供
+    pub signal_id: Option<u8>,
+}
+
+/// 单颗卫星的可见信息
+#[derive(Clone, Copy, Default, Debug)]
+pub struct SatelliteInfo {
+    pub prn: u16,
+    /// 仰角（度）
+    pub elevation: Option<i8>,
+    /// 方位角（度）
+    pub azimuth: Option<u16>,
+    /// 载噪比（dB-Hz）
+    pub snr: Option<u8>,
+}
+
+/// 伪距误差统计（米）
+#[derive(Default, Debug)]
+pub struct Gst {
+    pub utc: Option<NmeaTime>,
+    pub rms: Option<f32>,
+    /// 误差椭圆长半轴
+    pub semi_major: Option<f32>,
+    /// 误差椭圆短半轴
+    pub semi_minor: Option<f32>,
+    /// 误差椭圆长轴方向（度）
+    pub orientation: Option<f32>,
+    pub latitude_error: Option<f32>,
+    pub longitude_error: Option<f32>,
+    pub altitude_error: Option<f32>,
+}
+
+/// 时间和日期
+#[derive(Default, Debug)]
+pub struct Zda {
+    pub utc: Option<NmeaTime>,
+    pub date: Option<NmeaDate>,
+    pub zone_hours: Option<i8>,
+    pub zone_minutes: Option<u8>,
+}
+
+/// 真航向
+#[derive(Default, Debug)]
+pub struct Hdt {
+    pub heading: Option<f32>,
+}
+
+/// 多系统定位数据
+#[derive(Default, Debug)]
+pub struct Gns {
+    pub utc: Option<NmeaTime>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    /// 每个卫星系统一个字符
+    pub mode: String,
+    pub satellite: u8,
+    pub hdop: Option<f32>,
+    pub altitude: Option<f64>,
+    pub altitude_error: Option<f64>,
+    /// 差分龄期（秒）
+    pub differential_age: Option<f32>,
+    pub station_id: Option<u16>,
+    /// 导航状态，NMEA 4.1 以上提供
+    pub nav_status: Option<char>,
+}
+
+impl FromStr for NmeaSentence {
+    type Err = GpggaParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use GpggaParseError::*;
+        let body = strip_checksum(s);
+        let (head, body) = body.split_once(',').ok_or(WrongHead)?;
+        let kind = head
+            .strip_prefix('$')
+            .filter(|h| h.len() == 5)
+            .filter(|h| h.get(..2).and_then(|t| t.parse::<Talker>().ok()).is_some())
+            .and_then(|h| h.get(2..))
+            .ok_or(WrongHead)?;
+        let mut body = body.split(',');
+        match kind {
+            "GGA" => s.parse().map(Self::Gga),
+            "RMC" => {
+                let mut result = Rmc {
+                    utc: field!("utc"; body?),
+                    valid: match body.next() {
+                        Some("A") => true,
+                        Some("V") => false,
+                        Some(_) => return Err(FailToParse("valid")),
+                        None => return Err(LackOfField("valid")),
+                    },
+                    ..Default::default()
+                };
+                result.latitude = hemisphere(
+                    field!("latitude"; body?, parse_degree),
+                    body.next(),
+                    ["N", "S"],
+                    "latitude_dir",
+                )?;
+                result.longitude = hemisphere(
+                    field!("longitude"; body?, parse_degree),
+                    body.next(),
+                    ["E", "W"],
+                    "longitude_dir",
+                )?;
+                result.speed = field!("speed"; body?);
+                result.course = field!("course"; body?);
+                result.date = field!("date"; body?, parse_date);
+                let variation = field!("magnetic_variation"; body?);
+                result.magnetic_variation = match body.next() {
+                    Some("W") => variation.map(|v: f32| -v),
+                    _ => variation,
+                };
+                result.mode = body.next().and_then(|w| w.chars().next());
+                Ok(Self::Rmc(result))
+            }
+            "VTG" => {
+                // 每个数值后跟一个单位字段
+                let mut next = || {
+                    let word = body.next();
+                    body.next();
+                    word
+                };
+                let mut body = std::iter::from_fn(&mut next);
+                Ok(Self::Vtg(Vtg {
+                    course_true: field!("course_true"; body?),
+                    course_magnetic: field!("course_magnetic"; body?),
+                    speed_knots: field!("speed_knots"; body?),
+                    speed_kmh: field!("speed_kmh"; body?),
+                    mode: body.next().and_then(|w| w.chars().next()),
+                }))
+            }
+            "GSA" => {
+                let mut result = Gsa {
+                    selection: field!("selection"; body),
+                    fix: field!("fix"; body),
+                    ..Default::default()
+                };
+                for _ in 0..12 {
+                    if let Some(prn) = field!("satellite"; body?) {
+                        result.satellites.push(prn);
+                    }
+                }
+                result.pdop = field!("pdop"; body?);
+                result.hdop = field!("hdop"; body?);
+                result.vdop = field!("vdop"; body?);
+                result.system_id = body.next().and_then(|w| w.parse().ok());
+                Ok(Self::Gsa(result))
+            }
+            "GSV" => {
+                let mut result = Gsv {
+                    total: field!("total"; body),
+                    index: field!("index"; body),
+                    in_view: field!("in_view"; body),
+                    ..Default::default()
+                };
+                let rest = body.collect::<Vec<_>>();
+                let mut groups = rest.chunks_exact(4);
+                for group in &mut groups {
+                    let mut group = group.iter().copied();
+                    result.satellites.push(SatelliteInfo {
+                        prn: field!("prn"; group),
+                        elevation: field!("elevation"; group?),
+                        azimuth: field!("azimuth"; group?),
+                        snr: field!("snr"; group?),
+                    });
+                }
+                result.signal_id = match groups.remainder() {
+                    [] => None,
+                    [id] => id.parse().ok(),
+                    _ => return Err(FailToParse("satellite")),
+                };
+                Ok(Self::Gsv(result))
+            }
+            "GST" => Ok(Self::Gst(Gst {
+                utc: field!("utc"; body?),
+                rms: field!("rms"; body?),
+                semi_major: field!("semi_major"; body?),
+                semi_minor: field!("semi_minor"; body?),
+                orientation: field!("orientation"; body?),
+                latitude_error: field!("latitude_error"; body?),
+                longitude_error: field!("longitude_error"; body?),
+                altitude_error: field!("altitude_error"; body?),
+            })),
+            "ZDA" => {
+                let utc = field!("utc"; body?);
+                let day = field!("day"; body?);
+                let month = field!("month"; body?);
+                let year = field!("year"; body?);
+                Ok(Self::Zda(Zda {
+                    utc,
+                    date: match (year, month, day) {
+                        (Some(year), Some(month), Some(day)) => Some(NmeaDate { year, month, day }),
+                        _ => None,
+                    },
+                    zone_hours: field!("zone_hours"; body?),
+                    zone_minutes: field!("zone_minutes"; body?),
+                }))
+            }
+            "HDT" => Ok(Self::Hdt(Hdt {
+                heading: field!("heading"; body?),
+            })),
+            "GNS" => {
+                let mut result = Gns {
+                    utc: field!("utc"; body?),
+                    ..Default::default()
+                };
+                result.latitude = hemisphere(
+                    field!("latitude"; body?, parse_degree),
+                    body.next(),
+                    ["N", "S"],
+                    "latitude_dir",
+                )?;
+                result.longitude = hemisphere(
+                    field!("longitude"; body?, parse_degree),
+                    body.next(),
+                    ["E", "W"],
+                    "longitude_dir",
+                )?;
+                result.mode = match body.next() {
+                    Some(word) => word.into(),
+                    None => return Err(LackOfField("mode")),
+                };
+                result.satellite = field!("satellite"; body);
+                result.hdop = field!("hdop"; body?);
+                result.altitude = field!("altitude"; body?);
+                result.altitude_error = field!("altitude_error"; body?);
+                result.differential_age = field!("differential_age"; body?);
+                result.station_id = field!("station_id"; body?);
+                result.nav_status = body.next().and_then(|w| w.chars().next());
+                Ok(Self::Gns(result))
+            }
+            _ => Err(WrongHead),
+        }
+    }
+}
+
+/// 根据方向字段确定经纬度的符号
+fn hemisphere(
+    val: Option<f64>,
+    dir: Option<&str>,
+    [positive, negative]: [&str; 2],
+    name: &'static str,
+) -> Result<Option<f64>, GpggaParseError> {
+    use GpggaParseError::*;
+    match (val, dir) {
+        (_, None) => Err(LackOfField(name)),
+        (None, Some(_)) => Ok(None),
+        (Some(val), Some(dir)) if dir == positive => Ok(Some(val)),
+        (Some(val), Some(dir)) if dir == negative => Ok(Some(-val)),
+        (Some(_), Some(_)) => Err(FailToParse(name)),
+    }
+}
+
+/// ddmmyy 格式转日期
+fn parse_date(word: &str) -> Option<NmeaDate> {
+    if word.len() != 6 {
+        return None;
+    }
+    Some(NmeaDate {
+        day: word.get(0..2)?.parse().ok()?,
+        month: word.get(2..4)?.parse().ok()?,
+        year: 2000 + word.get(4..6)?.parse::<u16>().ok()?,
+    })
+}
+
+#[cfg(test)]
+mod t {
+    use super::*;
+
+    #[test]
+    fn test_rmc() {
+        let line =
+            "$GNRMC,060220.00,A,3959.55874779,N,11619.61828897,E,0.012,245.3,170921,6.5,W,D*0F";
+        match line.parse() {
+            Ok(NmeaSentence::Rmc(rmc)) => {
+                assert!(rmc.valid);
+                assert!((rmc.latitude.unwrap() - 39.992645796).abs() < 1e-8);
+                assert_eq!(Some(0.012), rmc.speed);
+                assert_eq!(
+                    Some(NmeaDate {
+                        year: 2021,
+                        month: 9,
+                        day: 17
+                    }),
+                    rmc.date
+                );
+                assert_eq!(Some(-6.5), rmc.magnetic_variation);
+                assert_eq!(Some('D'), rmc.mode);
+            }
+            _ => panic!(),
+        }
+
+        match "$GPRMC,,V,,,,,,,,,,N*53".parse() {
+            Ok(NmeaSentence::Rmc(rmc)) => {
+                assert!(!rmc.valid);
+                assert_eq!(None, rmc.latitude);
+                assert_eq!(None, rmc.date);
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn test_gsa_gsv() {
+        match "$GNGSA,A,3,10,16,26,27,,,,,,,,,1.6,0.9,1.3,1*33".parse() {
+            Ok(NmeaSentence::Gsa(gsa)) => {
+                assert_eq!(3, gsa.fix);
+                assert_eq!(vec![10, 16, 26, 27], gsa.satellites);
+                assert_eq!(Some(0.9), gsa.hdop);
+                assert_eq!(Some(1), gsa.system_id);
+            }
+            _ => panic!(),
+        }
+
+        match "$GPGSV,3,1,11,10,63,137,17,16,,,40,26,36,050,,27,05,284,42,1*6B".parse() {
+            Ok(NmeaSentence::Gsv(gsv)) => {
+                assert_eq!(3, gsv.total);
+                assert_eq!(11, gsv.in_view);
+                assert_eq!(4, gsv.satellites.len());
+                assert_eq!(None, gsv.satellites[1].elevation);
+                assert_eq!(Some(40), gsv.satellites[1].snr);
+                assert_eq!(None, gsv.satellites[2].snr);
+                assert_eq!(Some(1), gsv.signal_id);
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn test_others() {
+        match "$GPGST,060220.00,1.2,0.021,0.012,78.5,0.015,0.019,0.034*4B".parse() {
+            Ok(NmeaSentence::Gst(gst)) => {
+                assert_eq!(Some(0.021), gst.semi_major);
+                assert_eq!(Some(0.034), gst.altitude_error);
+            }
+            _ => panic!(),
+        }
+        match "$GPZDA,060220.00,17,09,2021,00,00*6F".parse() {
+            Ok(NmeaSentence::Zda(zda)) => assert_eq!(2021, zda.date.unwrap().year),
+            _ => panic!(),
+        }
+        match "$GPHDT,274.07,T*03".parse() {
+            Ok(NmeaSentence::Hdt(hdt)) => assert_eq!(Some(274.07), hdt.heading),
+            _ => panic!(),
+        }
+        match "$GNGNS,060220.00,3959.55874779,N,11619.61828897,E,RRN,17,0.9,60.139,-9.286,1.0,0001,V*3D"
+            .parse()
+        {
+            Ok(NmeaSentence::Gns(gns)) => {
+                assert_eq!("RRN", gns.mode);
+                assert_eq!(17, gns.satellite);
+                assert_eq!(Some(1), gns.station_id);
+                assert_eq!(Some('V'), gns.nav_status);
+            }
+            _ => panic!(),
+        }
+        assert!(matches!(
+            "$Xé12,1".parse::<NmeaSentence>(),
+            Err(GpggaParseError::WrongHead)
+        ));
+        assert!(parse_date("1é092").is_none());
+        assert!(matches!(
+            "$GPRMC,060220.00,A,é1.5,N,,,,,,,".parse::<NmeaSentence>(),
+            Err(GpggaParseError::FailToParse("latitude"))
+        ));
+        assert!(matches!(
+            "$GPXXX,1,2,3*00".parse::<NmeaSentence>(),
+            Err(GpggaParseError::WrongHead)
+        ));
+    }
+}
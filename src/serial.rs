@@ -1,18 +1,75 @@
-﻿use crate::nmea::Buffer;
+﻿use crate::{
+    command::{BoardCommand, CommandError},
+    demux::Demuxer,
+    event::{BoardEvent, BoardMessage},
+    nmea::Buffer,
+    novatel::LogBody,
+    port::PortFilter,
+    time::GnssClock,
+};
 use driver::Driver;
-use serial_port::{Port, PortKey, SerialPort};
+use serial_port::PortKey;
+#[cfg(not(test))]
+use serial_port::{Port, SerialPort};
 use std::{
+    collections::VecDeque,
+    marker::PhantomData,
     sync::{Arc, Weak},
     time::{Duration, Instant},
 };
+#[cfg(test)]
+use t::Port;
 
 const OPEN_TIMEOUT: Duration = Duration::from_millis(3000);
 const LINE_RECEIVE_TIMEOUT: Duration = Duration::from_millis(2500);
+const PROBE_TIMEOUT: Duration = Duration::from_millis(1500);
+const COMMAND_TIMEOUT: Duration = Duration::from_millis(2000);
+
+/// 自动检测时依次尝试的波特率
+pub const BAUD_RATES: [u32; 8] = [115200, 9600, 38400, 230400, 460800, 57600, 921600, 19200];
 
-pub struct RTKBoard {
+pub struct RTKBoard<C = Baud<115200>> {
     port: Arc<Port>,
-    buf: Buffer<256>,
+    buf: Demuxer<2048>,
     last_time: Instant,
+    /// 每次接收的第一个字节在字节流中的位置和接收时刻
+    arrivals: VecDeque<(u64, Instant)>,
+    gnss_clock: GnssClock,
+    /// 等待命令应答期间解析出的事件，[`Driver::join`] 先于新数据发出
+    pending: VecDeque<BoardEvent>,
+    baud_rate: u32,
+    _config: PhantomData<C>,
+}
+
+/// 板卡串口配置
+///
+/// 串口库只支持设置波特率，数据位、校验位和停止位固定为 8N1，不提供配置；
+/// 其他帧格式的板卡需要先用厂商工具改为 8N1。
+pub trait BoardConfig: 'static + Send {
+    /// 固定波特率，`None` 表示依次尝试 [`BAUD_RATES`]
+    const BAUD_RATE: Option<u32>;
+    /// 超过这个时间没有收到完整语句视为断开
+    const TIMEOUT: Duration = LINE_RECEIVE_TIMEOUT;
+    /// 只尝试满足任一条件的串口，为空时尝试所有串口
+    const FILTERS: &'static [PortFilter] = &[];
+    /// 打开后确认能收到校验正确的语句，自动检测波特率时总是确认
+    const PROBE: bool = false;
+    /// 输出 NovAtel 格式的二进制日志，否则只输出其中的定向日志
+    const BINARY_LOGS: bool = false;
+}
+
+/// 固定波特率
+pub struct Baud<const N: u32>;
+
+/// 自动检测波特率
+pub struct AutoBaud;
+
+impl<const N: u32> BoardConfig for Baud<N> {
+    const BAUD_RATE: Option<u32> = Some(N);
+}
+
+impl BoardConfig for AutoBaud {
+    const BAUD_RATE: Option<u32> = None;
 }
 
 pub struct RTCMReceiver(Weak<Port>);
@@ -25,38 +82,159 @@ impl RTCMReceiver {
     }
 }
 
-impl RTKBoard {
+impl<C> RTKBoard<C> {
     pub fn get_receiver(&self) -> RTCMReceiver {
         RTCMReceiver(Arc::downgrade(&self.port))
     }
+
+    /// 实际使用的波特率
+    #[inline]
+    pub fn baud_rate(&self) -> u32 {
+        self.baud_rate
+    }
+
+    /// 由 ZDA、RMC 和 GGA 维护的 UTC 时钟，可用于补偿串口延迟
+    #[inline]
+    pub fn gnss_clock(&self) -> &GnssClock {
+        &self.gnss_clock
+    }
+
+    #[inline]
+    pub fn gnss_clock_mut(&mut self) -> &mut GnssClock {
+        &mut self.gnss_clock
+    }
+}
+
+impl<C: BoardConfig> RTKBoard<C> {
+    /// 发送配置命令并等待应答
+    ///
+    /// 等待期间收到的其他数据照常解析，随后由 [`Driver::join`] 发出，可在回调中调用。
+    pub fn command(&mut self, command: &BoardCommand) -> Result<(), CommandError> {
+        let _ = self.port.write(&command.to_bytes());
+        let deadline = Instant::now() + COMMAND_TIMEOUT;
+        let mut received = Vec::new();
+        while Instant::now() < deadline {
+            let n = self.receive().ok_or(CommandError::Disconnected)?;
+            received.extend_from_slice(self.buf.tail(n));
+            while let Some(event) = self.next_event() {
+                self.pending.push_back(event);
+            }
+            if let Some(result) = command.check(&received) {
+                return result;
+            }
+            // 只保留最近的数据，应答不会很长
+            if received.len() > 4096 {
+                received.drain(..2048);
+            }
+        }
+        Err(CommandError::Timeout)
+    }
+
+    fn with_port(port: Port, baud_rate: u32) -> Self {
+        Self {
+            port: Arc::new(port),
+            buf: Demuxer::new(),
+            last_time: Instant::now(),
+            arrivals: VecDeque::new(),
+            gnss_clock: GnssClock::new(),
+            pending: VecDeque::new(),
+            baud_rate,
+            _config: PhantomData,
+        }
+    }
+
+    fn open(t: &PortKey, baud_rate: u32) -> Option<Port> {
+        Port::open(t, baud_rate, C::TIMEOUT.as_millis() as u32).ok()
+    }
+
+    /// 从串口接收一次，返回收到的长度
+    fn receive(&mut self) -> Option<usize> {
+        let position = self.buf.received();
+        let n = self.port.read(self.buf.write_buf()).filter(|n| *n > 0)?;
+        self.last_time = Instant::now();
+        self.arrivals.push_back((position, self.last_time));
+        self.buf.extend(n);
+        Some(n)
+    }
+
+    /// 从缓冲区中解析下一个事件并更新时钟，缓冲区中没有完整的帧时返回 `None`
+    fn next_event(&mut self) -> Option<BoardEvent> {
+        loop {
+            let (position, frame) = self.buf.parse_at()?;
+            // 帧头所在的那次接收
+            while matches!(self.arrivals.get(1), Some((p, _)) if *p <= position) {
+                self.arrivals.pop_front();
+            }
+            let arrival = self.arrivals.front().map_or(self.last_time, |(_, t)| *t);
+            let event = match BoardEvent::decode(arrival, frame) {
+                Some(BoardEvent {
+                    message: BoardMessage::Log(ref log),
+                    ..
+                }) if !C::BINARY_LOGS && !matches!(log.body, LogBody::Heading(_)) => continue,
+                Some(event) => event,
+                None => continue,
+            };
+            if let BoardMessage::Nmea(Ok(sentence)) = &event.message {
+                self.gnss_clock.update(sentence, arrival);
+            }
+            return Some(event);
+        }
+    }
 }
 
-impl Driver for RTKBoard {
+/// 检查串口能否收到校验正确的语句
+fn probe(port: &Port) -> bool {
+    let mut buf = Buffer::<256>::new();
+    let deadline = Instant::now() + PROBE_TIMEOUT;
+    while Instant::now() < deadline {
+        match port.read(buf.to_write()) {
+            Some(n) => {
+                buf.extend(n);
+                if buf.parse().is_some() {
+                    return true;
+                }
+            }
+            None => return false,
+        }
+    }
+    false
+}
+
+impl<C: BoardConfig> Driver for RTKBoard<C> {
     type Pacemaker = ();
     type Key = PortKey;
-    type Event = String;
+    type Event = BoardEvent;
 
     fn keys() -> Vec<Self::Key> {
-        Port::list().into_iter().map(|id| id.key).collect()
+        Port::list()
+            .into_iter()
+            .filter(|id| C::FILTERS.is_empty() || C::FILTERS.iter().any(|f| f.matches(id)))
+            .map(|id| id.key)
+            .collect()
     }
 
+    /// 打开串口的时间加上所有波特率的确认时间
     fn open_timeout() -> std::time::Duration {
-        OPEN_TIMEOUT
+        let probes = match C::BAUD_RATE {
+            Some(_) => C::PROBE as u32,
+            None => BAUD_RATES.len() as u32,
+        };
+        OPEN_TIMEOUT + PROBE_TIMEOUT * probes
     }
 
     fn new(t: &Self::Key) -> Option<(Self::Pacemaker, Self)> {
-        Port::open(t, 115200, LINE_RECEIVE_TIMEOUT.as_millis() as u32)
-            .ok()
-            .map(|port| {
-                (
-                    (),
-                    Self {
-                        port: Arc::new(port),
-                        buf: Buffer::new(),
-                        last_time: Instant::now(),
-                    },
-                )
-            })
+        let (port, baud_rate) = match C::BAUD_RATE {
+            Some(baud_rate) => (
+                Self::open(t, baud_rate).filter(|port| !C::PROBE || probe(port))?,
+                baud_rate,
+            ),
+            None => BAUD_RATES.iter().find_map(|&baud_rate| {
+                Self::open(t, baud_rate)
+                    .filter(probe)
+                    .map(|port| (port, baud_rate))
+            })?,
+        };
+        Some(((), Self::with_port(port, baud_rate)))
     }
 
     fn join<F>(&mut self, mut f: F) -> bool
@@ -65,30 +243,88 @@ impl Driver for RTKBoard {
     {
         let mut time = Instant::now();
         loop {
-            if let Some(line) = self.buf.parse() {
-                time = self.last_time;
-                let line = format!("{}\r\n", line);
+            // 回调中发送命令时解析出的事件先发出
+            if let Some(event) = self.pending.pop_front().or_else(|| self.next_event()) {
                 // 如果回调指示不要继续阻塞，立即退出
-                if !f(self, Some((time, line))) {
+                if !f(self, Some((event.time, event))) {
                     return true;
                 }
+                // 回调中的命令可能已接收了很久
+                time = self.last_time;
             }
-            // 解析超时
-            else if self.last_time > time + LINE_RECEIVE_TIMEOUT {
+            // 解析超时或接收失败
+            else if self.last_time > time + C::TIMEOUT || self.receive().is_none() {
                 return false;
             }
-            // 接收
-            else {
-                let buf = self.buf.to_write();
-                if let Some(n) = self.port.read(buf).filter(|n| *n > 0) {
-                    self.last_time = Instant::now();
-                    self.buf.extend(n);
-                }
-                // 接收失败
-                else {
-                    return false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod t {
+    use super::*;
+    use crate::command::Vendor;
+    use std::sync::Mutex;
+
+    /// 依次返回预设数据的串口
+    pub(super) struct Port {
+        input: Mutex<VecDeque<&'static [u8]>>,
+        output: Mutex<Vec<u8>>,
+    }
+
+    impl Port {
+        pub fn list() -> Vec<serial_port::SerialId> {
+            Vec::new()
+        }
+
+        pub fn open(_: &PortKey, _: u32, _: u32) -> Result<Self, ()> {
+            Err(())
+        }
+
+        pub fn read(&self, buf: &mut [u8]) -> Option<usize> {
+            let data = self.input.lock().unwrap().pop_front()?;
+            buf[..data.len()].copy_from_slice(data);
+            Some(data.len())
+        }
+
+        pub fn write(&self, buf: &[u8]) -> Option<usize> {
+            self.output.lock().unwrap().extend_from_slice(buf);
+            Some(buf.len())
+        }
+    }
+
+    struct Fast;
+
+    impl BoardConfig for Fast {
+        const BAUD_RATE: Option<u32> = Some(115200);
+        const TIMEOUT: Duration = Duration::from_millis(100);
+    }
+
+    #[test]
+    fn test_command_in_join() {
+        let port = Port {
+            input: Mutex::new(VecDeque::from([
+                &b"$GPGGA,,,,,,0,00,99.99,,,,,,*48\r\n"[..],
+                b"$GPGGA,,,,,,0,00,99.99,,,,,,*48\r\n$command,GPGGA COM1 1,response: OK*2A\r\n",
+            ])),
+            output: Mutex::new(Vec::new()),
+        };
+        let mut board = RTKBoard::<Fast>::with_port(port, 115200);
+        let cmd = BoardCommand::nmea_rate(Vendor::Unicore, "GPGGA", "COM1", 1.0).unwrap();
+        let mut count = 0;
+        let alive = board.join(|board, event| {
+            if matches!(&event, Some((_, e)) if e.gpgga().is_some()) {
+                count += 1;
+                if count == 1 {
+                    // 命令耗时超过接收超时
+                    std::thread::sleep(Fast::TIMEOUT * 2);
+                    assert!(board.command(&cmd).is_ok());
                 }
             }
-        }
+            true
+        });
+        assert!(!alive);
+        assert_eq!(2, count);
+        assert_eq!(cmd.to_bytes(), *board.port.output.lock().unwrap());
     }
 }
@@ -0,0 +1,282 @@
+use crate::{
+    account::{AccountProvider, ConfigError, Failover},
+    backoff::Backoff,
+    event::BoardEvent,
+    network::{QXWZService, DATA_TIMEOUT},
+    ntrip::{NtripCaster, NtripError},
+    serial::{Baud, BoardConfig, RTCMReceiver, RTKBoard},
+    upload::{GpggaUploader, UPLOAD_INTERVAL},
+};
+use async_std::task;
+use driver::{SupervisorEventForSingle::*, SupervisorForSingle};
+use serial_port::PortKey;
+use std::{
+    marker::PhantomData,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+const BOARD_RETRY: Duration = Duration::from_secs(1);
+
+/// 同时管理板卡和差分服务的会话
+///
+/// 差分数据转发给板卡，板卡的有效位置定时上传给服务器，任一端断开都会自动重连。
+pub struct RtkSession<A, C = Baud<115200>> {
+    account: A,
+    uploader: GpggaUploader,
+    backoff: Backoff,
+    data_timeout: Duration,
+    active: ActiveAccount,
+    _board: PhantomData<C>,
+}
+
+/// 当前使用的账号，可以在会话运行时查询
+#[derive(Clone, Default, Debug)]
+pub struct ActiveAccount(Arc<Mutex<Option<NtripCaster>>>);
+
+impl ActiveAccount {
+    /// 已连接的服务，未连接时为 `None`
+    #[inline]
+    pub fn get(&self) -> Option<NtripCaster> {
+        self.0.lock().unwrap().clone()
+    }
+
+    #[inline]
+    fn set(&self, caster: Option<NtripCaster>) {
+        *self.0.lock().unwrap() = caster;
+    }
+}
+
+/// 会话事件
+#[derive(Debug)]
+pub enum SessionEvent {
+    BoardConnected(PortKey),
+    BoardDisconnected,
+    BoardConnectFailed,
+    CasterConnected(NtripCaster),
+    CasterDisconnected,
+    /// 连接后没有收到任何差分数据就断开，可能是未上传位置、挂载点没有数据或账号在别处登录，
+    /// 不影响该账号之后的使用
+    CasterNoData(NtripCaster),
+    /// 连接服务器失败，附带失败原因，没有可用账号时为 `None`
+    CasterConnectFailed(Option<NtripError>),
+    /// 读取账号失败，如配置文件有误
+    AccountFailed(ConfigError),
+    /// 板卡输出，包括位置和定向
+    Board(BoardEvent),
+}
+
+/// 两个线程共用的事件发送端，任一线程发现接收端已释放后两个线程都停止
+#[derive(Clone)]
+struct Events {
+    sender: Sender<SessionEvent>,
+    closed: Arc<AtomicBool>,
+}
+
+impl Events {
+    /// 发送事件，返回是否继续运行
+    fn send(&self, event: SessionEvent) -> bool {
+        if self.is_open() && self.sender.send(event).is_ok() {
+            true
+        } else {
+            self.closed.store(true, Ordering::Relaxed);
+            false
+        }
+    }
+
+    #[inline]
+    fn is_open(&self) -> bool {
+        !self.closed.load(Ordering::Relaxed)
+    }
+}
+
+impl<A> RtkSession<A> {
+    /// 使用默认的板卡配置，每 [`UPLOAD_INTERVAL`] 上传一次位置，
+    /// 周期可用 [`with_upload_interval`](Self::with_upload_interval) 修改
+    pub fn new(account: A) -> Self {
+        Self {
+            account,
+            uploader: GpggaUploader::new(UPLOAD_INTERVAL),
+            backoff: Backoff::default(),
+            data_timeout: DATA_TIMEOUT,
+            active: Default::default(),
+            _board: PhantomData,
+        }
+    }
+}
+
+impl<A, C> RtkSession<A, C> {
+    /// 更换板卡配置
+    #[inline]
+    pub fn with_board<B>(self) -> RtkSession<A, B> {
+        RtkSession {
+            account: self.account,
+            uploader: self.uploader,
+            backoff: self.backoff,
+            data_timeout: self.data_timeout,
+            active: self.active,
+            _board: PhantomData,
+        }
+    }
+
+    /// 设置上传位置的周期，默认为 [`UPLOAD_INTERVAL`]
+    #[inline]
+    pub fn with_upload_interval(self, interval: Duration) -> Self {
+        task::block_on(self.uploader.set_interval(interval));
+        self
+    }
+
+    /// 设置连接服务器失败后的重试间隔
+    #[inline]
+    pub fn with_backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// 设置差分数据超时，超过这个时间没有收到差分数据视为断开，默认 10 秒
+    #[inline]
+    pub fn with_data_timeout(mut self, timeout: Duration) -> Self {
+        self.data_timeout = timeout;
+        self
+    }
+
+    #[inline]
+    pub fn uploader(&self) -> &GpggaUploader {
+        &self.uploader
+    }
+
+    #[inline]
+    pub fn active(&self) -> &ActiveAccount {
+        &self.active
+    }
+}
+
+impl<A: AccountProvider, C: BoardConfig> RtkSession<A, C> {
+    /// 在后台线程中运行，返回事件流
+    ///
+    /// 账号从本会话随机选定的位置开始依次尝试，共用账号池的设备因此分散到不同账号上，
+    /// 断开后仍从这个位置重新尝试。账号或密码错误的账号暂停一段时间。
+    ///
+    /// 账号已被占用不单独识别：缺少千寻对此应答的实测样本，这种情况报告为
+    /// [`SessionEvent::CasterNoData`]，不暂停该账号。
+    /// 事件流的接收端释放后，两个线程在下一个事件或下一帧差分数据时退出。
+    pub fn spawn(self) -> Receiver<SessionEvent> {
+        let (sender, receiver) = mpsc::channel();
+        let events = Events {
+            sender,
+            closed: Default::default(),
+        };
+        let rtcm: Arc<Mutex<Option<RTCMReceiver>>> = Default::default();
+        {
+            let events = events.clone();
+            let rtcm = rtcm.clone();
+            let uploader = self.uploader.clone();
+            let mut backoff = self.backoff.clone();
+            let active = self.active.clone();
+            let data_timeout = self.data_timeout;
+            let account = self.account;
+            let mut failover = Failover::default();
+            thread::spawn(move || loop {
+                let casters = match account.casters() {
+                    Ok(casters) => {
+                        let casters = failover.available(casters);
+                        if casters.is_empty()
+                            && !events.send(SessionEvent::CasterConnectFailed(None))
+                        {
+                            return;
+                        }
+                        casters
+                    }
+                    Err(e) => {
+                        if !events.send(SessionEvent::AccountFailed(e)) {
+                            return;
+                        }
+                        vec![]
+                    }
+                };
+                for caster in casters {
+                    let mut service = match QXWZService::connect(&caster, data_timeout) {
+                        Ok(service) => service,
+                        Err(e) => {
+                            failover.fail(&caster, &e);
+                            if !events.send(SessionEvent::CasterConnectFailed(Some(e))) {
+                                return;
+                            }
+                            continue;
+                        }
+                    };
+                    task::block_on(uploader.set_sender(Some(service.get_sender())));
+                    active.set(Some(caster.clone()));
+                    if !events.send(SessionEvent::CasterConnected(caster.clone())) {
+                        return;
+                    }
+                    let mut received = false;
+                    let stopped = service.run(|_, frame| {
+                        if let Some((_, frame)) = frame {
+                            // 收到数据才算连接成功，握手后立即断开的服务器仍按退避间隔重试
+                            if !received {
+                                received = true;
+                                backoff.reset();
+                            }
+                            if let Some(ref receiver) = *rtcm.lock().unwrap() {
+                                receiver.receive(&frame);
+                            }
+                        }
+                        events.is_open()
+                    });
+                    task::block_on(uploader.set_sender(None));
+                    active.set(None);
+                    if stopped {
+                        return;
+                    }
+                    // 换下一个账号试试，但不暂停这个账号
+                    if !received {
+                        if !events.send(SessionEvent::CasterNoData(caster)) {
+                            return;
+                        }
+                        continue;
+                    }
+                    if !events.send(SessionEvent::CasterDisconnected) {
+                        return;
+                    }
+                    break;
+                }
+                thread::sleep(backoff.next_delay());
+                if !events.is_open() {
+                    return;
+                }
+            });
+        }
+        let uploader = self.uploader;
+        thread::spawn(move || {
+            SupervisorForSingle::<RTKBoard<C>>::default().join(|e| match e {
+                Connected(port, board) => {
+                    *rtcm.lock().unwrap() = Some(board.get_receiver());
+                    events.send(SessionEvent::BoardConnected(port))
+                }
+                Disconnected => {
+                    *rtcm.lock().unwrap() = None;
+                    events.send(SessionEvent::BoardDisconnected)
+                }
+                Event(_, Some((_, event))) => {
+                    if let Some(gpgga) = event.gpgga() {
+                        task::block_on(uploader.update(gpgga));
+                    }
+                    events.send(SessionEvent::Board(event))
+                }
+                Event(_, None) => events.is_open(),
+                ConnectFailed => {
+                    let alive = events.send(SessionEvent::BoardConnectFailed);
+                    thread::sleep(BOARD_RETRY);
+                    alive
+                }
+            });
+        });
+        receiver
+    }
+}
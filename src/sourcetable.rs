@@ -0,0 +1,219 @@
+use crate::Gpgga;
+use std::str::FromStr;
+
+/// NTRIP 源列表
+#[derive(Default, Debug)]
+pub struct SourceTable {
+    pub streams: Vec<StreamRecord>,
+    pub casters: Vec<CasterRecord>,
+    pub networks: Vec<NetworkRecord>,
+}
+
+/// `STR` 记录，即一个挂载点
+#[derive(Clone, Default, Debug)]
+pub struct StreamRecord {
+    pub mountpoint: String,
+    pub identifier: String,
+    pub format: String,
+    pub format_details: String,
+    /// 0：无载波相位，1：L1，2：L1 + L2
+    pub carrier: u8,
+    pub nav_system: String,
+    pub network: String,
+    pub country: String,
+    /// 纬度和经度，无法解析时为 `None`
+    pub position: Option<(f64, f64)>,
+    /// 是否需要客户端上传 GGA
+    pub nmea: bool,
+    /// 是否网络解（VRS 等），否则为单基站
+    pub network_solution: bool,
+    pub generator: String,
+    pub compression: String,
+    /// N：无，B：Basic，D：Digest
+    pub authentication: String,
+    pub fee: bool,
+    pub bitrate: u32,
+}
+
+/// `CAS` 记录，即另一个服务器
+#[derive(Clone, Default, Debug)]
+pub struct CasterRecord {
+    pub host: String,
+    pub port: u16,
+    pub identifier: String,
+    pub operator: String,
+    pub nmea: bool,
+    pub country: String,
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+/// `NET` 记录，即一个参考站网络
+#[derive(Clone, Default, Debug)]
+pub struct NetworkRecord {
+    pub identifier: String,
+    pub operator: String,
+    pub authentication: String,
+    pub fee: bool,
+    pub web: String,
+}
+
+impl SourceTable {
+    /// 离给定位置最近的挂载点，位置无效时返回 `None`，没有位置的挂载点不参与比较
+    pub fn nearest(&self, gpgga: &Gpgga) -> Option<&StreamRecord> {
+        let (latitude, longitude) = (gpgga.latitude?, gpgga.longitude?);
+        self.streams
+            .iter()
+            .filter_map(|s| {
+                let (lat, lon) = s.position?;
+                Some((s, distance(latitude, longitude, lat, lon)))
+            })
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(s, _)| s)
+    }
+}
+
+impl FromStr for SourceTable {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut result = Self::default();
+        for line in s.lines() {
+            match line.split_once(';') {
+                Some(("STR", _)) => result.streams.extend(line.parse()),
+                Some(("CAS", _)) => result.casters.extend(line.parse()),
+                Some(("NET", _)) => result.networks.extend(line.parse()),
+                Some(_) => {}
+                None if line.trim() == "ENDSOURCETABLE" => return Ok(result),
+                None => {}
+            }
+        }
+        Err(())
+    }
+}
+
+impl FromStr for StreamRecord {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut body = s.strip_prefix("STR;").ok_or(())?.split(';');
+        let mut next = || body.next().unwrap_or_default().to_string();
+        let result = Self {
+            mountpoint: next(),
+            identifier: next(),
+            format: next(),
+            format_details: next(),
+            carrier: next().parse().unwrap_or_default(),
+            nav_system: next(),
+            network: next(),
+            country: next(),
+            position: position(&next(), &next()),
+            nmea: next() == "1",
+            network_solution: next() == "1",
+            generator: next(),
+            compression: next(),
+            authentication: next(),
+            fee: next() == "Y",
+            bitrate: next().parse().unwrap_or_default(),
+        };
+        if result.mountpoint.is_empty() {
+            Err(())
+        } else {
+            Ok(result)
+        }
+    }
+}
+
+impl FromStr for CasterRecord {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut body = s.strip_prefix("CAS;").ok_or(())?.split(';');
+        let mut next = || body.next().unwrap_or_default().to_string();
+        Ok(Self {
+            host: next(),
+            port: next().parse().map_err(|_| ())?,
+            identifier: next(),
+            operator: next(),
+            nmea: next() == "1",
+            country: next(),
+            latitude: next().parse().unwrap_or_default(),
+            longitude: next().parse().unwrap_or_default(),
+        })
+    }
+}
+
+impl FromStr for NetworkRecord {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut body = s.strip_prefix("NET;").ok_or(())?.split(';');
+        let mut next = || body.next().unwrap_or_default().to_string();
+        Ok(Self {
+            identifier: next(),
+            operator: next(),
+            authentication: next(),
+            fee: next() == "Y",
+            web: next(),
+        })
+    }
+}
+
+fn position(latitude: &str, longitude: &str) -> Option<(f64, f64)> {
+    let latitude: f64 = latitude.parse().ok()?;
+    let longitude: f64 = longitude.parse().ok()?;
+    ((-90.0..=90.0).contains(&latitude) && (-180.0..=180.0).contains(&longitude))
+        .then_some((latitude, longitude))
+}
+
+/// 两点间的大圆距离（米）
+fn distance(lat0: f64, lon0: f64, lat1: f64, lon1: f64) -> f64 {
+    const R: f64 = 6371008.8;
+    let (lat0, lat1) = (lat0.to_radians(), lat1.to_radians());
+    let d_lat = lat1 - lat0;
+    let d_lon = (lon1 - lon0).to_radians();
+    let a = (d_lat / 2.0).sin().powi(2) + lat0.cos() * lat1.cos() * (d_lon / 2.0).sin().powi(2);
+    2.0 * R * a.sqrt().asin()
+}
+
+#[test]
+fn test_parse() {
+    const TABLE: &str = "\
+CAS;rtk2go.com;2101;NtripInfoCaster;BKG;0;DEU;50.12;8.69;http://www.rtk2go.com\r\n\
+NET;EUREF;EUREF;B;N;http://www.epncb.oma.be;;;\r\n\
+STR;BEIJ;Beijing;RTCM 3.3;1005(10),1077(1);2;GPS+GLO+GAL+BDS;IGS;CHN;39.61;115.89;0;0;Trimble;none;B;N;9600;\r\n\
+STR;SHAO;Shanghai;RTCM 3.2;1006(10),1127(1);2;GPS+BDS;IGS;CHN;31.10;121.20;1;1;Septentrio;none;B;Y;4800;\r\n\
+STR;NOPOS;Unknown;RTCM 3.2;1005(10);2;GPS;;;;;0;0;;none;B;N;0;\r\n\
+ENDSOURCETABLE\r\n";
+
+    let table = TABLE.parse::<SourceTable>().unwrap();
+    assert_eq!(1, table.casters.len());
+    assert_eq!(2101, table.casters[0].port);
+    assert_eq!(1, table.networks.len());
+    assert_eq!(3, table.streams.len());
+
+    let beij = &table.streams[0];
+    assert_eq!("BEIJ", beij.mountpoint);
+    assert_eq!("RTCM 3.3", beij.format);
+    assert_eq!(2, beij.carrier);
+    assert_eq!("GPS+GLO+GAL+BDS", beij.nav_system);
+    assert!(!beij.nmea);
+    assert_eq!(9600, beij.bitrate);
+    assert!(table.streams[1].nmea);
+    assert!(table.streams[1].fee);
+    assert_eq!(None, table.streams[2].position);
+
+    let gpgga = Gpgga {
+        latitude: Some(39.9),
+        longitude: Some(116.3),
+        ..Default::default()
+    };
+    assert_eq!("BEIJ", table.nearest(&gpgga).unwrap().mountpoint);
+
+    let gpgga = Gpgga {
+        latitude: Some(0.0),
+        longitude: Some(0.0),
+        ..Default::default()
+    };
+    assert_ne!("NOPOS", table.nearest(&gpgga).unwrap().mountpoint);
+}
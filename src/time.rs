@@ -0,0 +1,414 @@
+use crate::{sentence::NmeaSentence, NmeaDate};
+use std::{
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+    str::FromStr,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+const SECONDS_PER_DAY: f64 = 86400.0;
+const SECONDS_PER_WEEK: f64 = 604800.0;
+/// GPS 时间零点 1980-01-06 的 Unix 时间
+const GPS_EPOCH: f64 = 315964800.0;
+/// 2017 年以来 GPS 时间超前 UTC 的秒数
+pub const LEAP_SECONDS: u32 = 18;
+/// 最小延迟估计每秒允许上漂的量，覆盖两个时钟的频率差
+const DRIFT_ALLOWANCE: f64 = 1e-4;
+
+/// NMEA 语句中 `hhmmss.ss` 格式的 UTC 时间
+///
+/// 比较时不考虑小数位数。
+#[derive(Clone, Copy, Default, Debug)]
+pub struct NmeaTime {
+    pub hour: u8,
+    pub minute: u8,
+    /// 闰秒时为 60
+    pub second: u8,
+    pub nanos: u32,
+    /// 小数位数，解析时记录，输出时沿用
+    pub digits: u8,
+}
+
+impl NmeaTime {
+    /// 当日秒数
+    #[inline]
+    pub fn seconds_of_day(&self) -> f64 {
+        (self.hour as u32 * 3600 + self.minute as u32 * 60 + self.second as u32) as f64
+            + self.nanos as f64 * 1e-9
+    }
+
+    /// 由当日秒数构造，保留两位小数，超出一天返回 `None`
+    pub fn from_seconds_of_day(seconds: f64) -> Option<Self> {
+        if !(0.0..SECONDS_PER_DAY).contains(&seconds) {
+            return None;
+        }
+        let whole = seconds.floor() as u32;
+        Some(Self {
+            hour: (whole / 3600) as u8,
+            minute: (whole / 60 % 60) as u8,
+            second: (whole % 60) as u8,
+            nanos: (((seconds - whole as f64) * 1e9).round() as u32).min(999_999_999),
+            digits: 2,
+        })
+    }
+
+    #[inline]
+    fn key(&self) -> (u8, u8, u8, u32) {
+        (self.hour, self.minute, self.second, self.nanos)
+    }
+}
+
+impl PartialEq for NmeaTime {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for NmeaTime {}
+
+impl PartialOrd for NmeaTime {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for NmeaTime {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+impl Hash for NmeaTime {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key().hash(state)
+    }
+}
+
+impl FromStr for NmeaTime {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (hms, fraction) = s.split_once('.').unwrap_or((s, ""));
+        if hms.len() != 6
+            || !hms.bytes().all(|b| b.is_ascii_digit())
+            || fraction.len() > 9
+            || !fraction.bytes().all(|b| b.is_ascii_digit())
+        {
+            return Err(());
+        }
+        let two = |i: usize| hms[i..i + 2].parse::<u8>().unwrap();
+        let (hour, minute, second) = (two(0), two(2), two(4));
+        if hour > 23 || minute > 59 || second > 60 {
+            return Err(());
+        }
+        let nanos = fraction
+            .bytes()
+            .chain(std::iter::repeat(b'0'))
+            .take(9)
+            .fold(0, |n, b| n * 10 + (b - b'0') as u32);
+        Ok(Self {
+            hour,
+            minute,
+            second,
+            nanos,
+            digits: fraction.len() as u8,
+        })
+    }
+}
+
+impl fmt::Display for NmeaTime {
+    /// 默认保留 [`digits`](Self::digits) 位小数，可用精度指定
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:02}{:02}{:02}", self.hour, self.minute, self.second)?;
+        let digits = f.precision().unwrap_or(self.digits as usize).min(9);
+        if digits > 0 {
+            let fraction = self.nanos / 10u32.pow(9 - digits as u32);
+            write!(f, ".{:0width$}", fraction, width = digits)?;
+        }
+        Ok(())
+    }
+}
+
+/// 由 UTC 时间估计对应的单调时钟时刻
+///
+/// 假设串口延迟有一个固定的下限，取一段时间内接收时刻与 UTC 之差的最小值作为对应关系，
+/// 超出的部分即为这条语句的额外延迟。
+/// UTC 时间是连续的秒数，如 Unix 秒数，不处理跨过零点。
+#[derive(Clone, Default, Debug)]
+pub struct UtcClock {
+    /// 第一个样本的接收时刻和 UTC 秒数
+    anchor: Option<(Instant, f64)>,
+    /// 相对于锚点的最小延迟（秒）
+    offset: f64,
+    last_arrival: Option<Instant>,
+}
+
+impl UtcClock {
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            anchor: None,
+            offset: 0.0,
+            last_arrival: None,
+        }
+    }
+
+    /// 加入一个样本，返回该样本的额外延迟
+    pub fn update(&mut self, utc: f64, arrival: Instant) -> Duration {
+        let (anchor, utc0) = match self.anchor {
+            Some(anchor) => anchor,
+            None => {
+                self.anchor = Some((arrival, utc));
+                self.last_arrival = Some(arrival);
+                return Duration::ZERO;
+            }
+        };
+        let elapsed = arrival.saturating_duration_since(anchor).as_secs_f64();
+        let sample = elapsed - (utc - utc0);
+        let since_last = self
+            .last_arrival
+            .map_or(0.0, |t| arrival.saturating_duration_since(t).as_secs_f64());
+        self.offset = (self.offset + since_last * DRIFT_ALLOWANCE).min(sample);
+        self.last_arrival = Some(arrival);
+        Duration::from_secs_f64(sample - self.offset)
+    }
+
+    /// UTC 时刻对应的单调时钟时刻，尚无样本时返回 `None`
+    pub fn to_instant(&self, utc: f64) -> Option<Instant> {
+        let (anchor, utc0) = self.anchor?;
+        let delta = utc - utc0 + self.offset;
+        if delta >= 0.0 {
+            anchor.checked_add(Duration::from_secs_f64(delta))
+        } else {
+            anchor.checked_sub(Duration::from_secs_f64(-delta))
+        }
+    }
+
+    /// 单调时钟时刻对应的 UTC 秒数
+    pub fn to_utc(&self, instant: Instant) -> Option<f64> {
+        let (anchor, utc0) = self.anchor?;
+        let elapsed = match instant.checked_duration_since(anchor) {
+            Some(d) => d.as_secs_f64(),
+            None => -anchor.duration_since(instant).as_secs_f64(),
+        };
+        Some(utc0 + elapsed - self.offset)
+    }
+}
+
+/// GPS 周和周内秒
+#[derive(Clone, Copy, PartialEq, PartialOrd, Debug)]
+pub struct GpsTime {
+    /// 从 1980-01-06 起的完整周数，不按 1024 翻转
+    pub week: u32,
+    pub seconds: f64,
+}
+
+impl GpsTime {
+    /// 由 UTC 时间换算，`leap_seconds` 为 GPS 超前 UTC 的秒数
+    pub fn from_utc(time: SystemTime, leap_seconds: u32) -> Option<Self> {
+        let unix = time.duration_since(UNIX_EPOCH).ok()?.as_secs_f64();
+        let gps = unix + leap_seconds as f64 - GPS_EPOCH;
+        if gps < 0.0 {
+            return None;
+        }
+        let week = (gps / SECONDS_PER_WEEK).floor();
+        Some(Self {
+            week: week as u32,
+            seconds: gps - week * SECONDS_PER_WEEK,
+        })
+    }
+
+    pub fn to_utc(&self, leap_seconds: u32) -> SystemTime {
+        let gps = self.week as f64 * SECONDS_PER_WEEK + self.seconds;
+        UNIX_EPOCH + Duration::from_secs_f64(gps + GPS_EPOCH - leap_seconds as f64)
+    }
+}
+
+/// 由 NMEA 语句维护的 UTC 时钟
+///
+/// 日期来自 ZDA 或 RMC，GGA 等只有时间的语句使用最近的日期，跨过零点时自动进入下一天，
+/// 收到日期之前不估计对应关系。闰秒时刻 `23:59:60` 与次日零点重合。
+#[derive(Clone, Debug)]
+pub struct GnssClock {
+    /// 1970-01-01 起的天数
+    day: Option<i64>,
+    last_seconds: f64,
+    leap_seconds: u32,
+    /// 以 Unix 秒数为样本
+    clock: UtcClock,
+    /// 最近一条语句的额外延迟
+    latency: Duration,
+}
+
+impl Default for GnssClock {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GnssClock {
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            day: None,
+            last_seconds: 0.0,
+            leap_seconds: LEAP_SECONDS,
+            clock: UtcClock::new(),
+            latency: Duration::ZERO,
+        }
+    }
+
+    #[inline]
+    pub fn leap_seconds(&self) -> u32 {
+        self.leap_seconds
+    }
+
+    /// 设置 GPS 超前 UTC 的秒数，默认为 [`LEAP_SECONDS`]
+    #[inline]
+    pub fn set_leap_seconds(&mut self, leap_seconds: u32) {
+        self.leap_seconds = leap_seconds;
+    }
+
+    /// 最近一条带时间的语句超出最小串口延迟的部分，可用于补偿延迟
+    #[inline]
+    pub fn latency(&self) -> Duration {
+        self.latency
+    }
+
+    /// 加入一条语句，返回其 UTC 时间，尚不知道日期时返回 `None`
+    pub fn update(&mut self, sentence: &NmeaSentence, arrival: Instant) -> Option<SystemTime> {
+        let (utc, date) = match sentence {
+            NmeaSentence::Gga(gga) => (gga.utc?, None),
+            NmeaSentence::Rmc(rmc) => (rmc.utc?, rmc.date),
+            NmeaSentence::Zda(zda) => (zda.utc?, zda.date),
+            NmeaSentence::Gns(gns) => (gns.utc?, None),
+            _ => return None,
+        };
+        let seconds = utc.seconds_of_day();
+        match date {
+            Some(date) => self.day = Some(days_from_civil(date)),
+            // 比上一条早半天以上，说明跨过了零点
+            None if seconds + SECONDS_PER_DAY / 2.0 < self.last_seconds => {
+                self.day = self.day.map(|day| day + 1)
+            }
+            None => {}
+        }
+        self.last_seconds = seconds;
+        let unix = self.day? as f64 * SECONDS_PER_DAY + seconds;
+        self.latency = self.clock.update(unix, arrival);
+        Some(UNIX_EPOCH + Duration::from_secs_f64(unix))
+    }
+
+    /// 主机时刻对应的 UTC 时间
+    pub fn utc_at(&self, instant: Instant) -> Option<SystemTime> {
+        let unix = self.clock.to_utc(instant)?;
+        Some(UNIX_EPOCH + Duration::from_secs_f64(unix.max(0.0)))
+    }
+
+    /// 主机时刻对应的 GPS 时间
+    #[inline]
+    pub fn gps_at(&self, instant: Instant) -> Option<GpsTime> {
+        GpsTime::from_utc(self.utc_at(instant)?, self.leap_seconds)
+    }
+
+    /// UTC 时间对应的主机时刻
+    pub fn instant_of(&self, time: SystemTime) -> Option<Instant> {
+        let unix = time.duration_since(UNIX_EPOCH).ok()?.as_secs_f64();
+        self.clock.to_instant(unix)
+    }
+}
+
+/// 公历日期到 1970-01-01 的天数
+fn days_from_civil(date: NmeaDate) -> i64 {
+    let (m, d) = (date.month as i64, date.day as i64);
+    let y = date.year as i64 - if m <= 2 { 1 } else { 0 };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * (m + if m > 2 { -3 } else { 9 }) + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+#[cfg(test)]
+mod t {
+    use super::*;
+
+    #[test]
+    fn test_nmea_time() {
+        let time: NmeaTime = "235959.95".parse().unwrap();
+        assert_eq!(950_000_000, time.nanos);
+        assert_eq!("235959.95", time.to_string());
+        assert_eq!("235959.950", format!("{:.3}", time));
+        assert_eq!(86399.95, time.seconds_of_day());
+        assert!(time > "235959.9".parse().unwrap());
+        assert_eq!(Ok(time), "235959.95".parse());
+        assert_eq!(Some(time), NmeaTime::from_seconds_of_day(86399.95));
+        assert_eq!(
+            3723.0,
+            "010203".parse::<NmeaTime>().unwrap().seconds_of_day()
+        );
+        for text in ["010203", "010203.5", "010203.456"] {
+            assert_eq!(text, text.parse::<NmeaTime>().unwrap().to_string());
+        }
+        assert!("240000.00".parse::<NmeaTime>().is_err());
+        assert!("12345.6".parse::<NmeaTime>().is_err());
+    }
+
+    #[test]
+    fn test_gnss_clock() {
+        let date = |year, month, day| NmeaDate { year, month, day };
+        assert_eq!(0, days_from_civil(date(1970, 1, 1)));
+        assert_eq!(19358, days_from_civil(date(2023, 1, 1)));
+
+        let t0 = Instant::now();
+        let mut clock = GnssClock::new();
+        let gga: NmeaSentence = "$GPGGA,235959.00,,,,,0,00,99.99,,,,,,*67".parse().unwrap();
+        assert_eq!(None, clock.update(&gga, t0));
+        let zda: NmeaSentence = "$GPZDA,235959.50,31,12,2022,00,00*61".parse().unwrap();
+        let time = clock.update(&zda, t0).unwrap();
+        assert_eq!(
+            days_from_civil(date(2023, 1, 1)) as f64 * SECONDS_PER_DAY - 0.5,
+            time.duration_since(UNIX_EPOCH).unwrap().as_secs_f64()
+        );
+        // 跨过零点
+        let gga: NmeaSentence = "$GPGGA,000000.50,,,,,0,00,99.99,,,,,,*63".parse().unwrap();
+        let time = clock.update(&gga, t0 + Duration::from_secs(1)).unwrap();
+        let gps = GpsTime::from_utc(time, LEAP_SECONDS).unwrap();
+        assert_eq!(2243, gps.week);
+        assert!((gps.seconds - (LEAP_SECONDS as f64 + 0.5)).abs() < 1e-6);
+        let error = |t: SystemTime| {
+            t.duration_since(time)
+                .or_else(|_| time.duration_since(t))
+                .unwrap()
+        };
+        assert!(error(gps.to_utc(LEAP_SECONDS)) < Duration::from_micros(1));
+        let utc = clock.utc_at(t0 + Duration::from_secs(1)).unwrap();
+        assert!(error(utc) < Duration::from_micros(1));
+        // 相差半天以上的时间不会被折回同一天
+        let later = clock
+            .instant_of(time + Duration::from_secs(20 * 3600))
+            .unwrap();
+        let expected = t0 + Duration::from_secs(20 * 3600 + 1);
+        assert!(later.duration_since(expected) < Duration::from_micros(1));
+        assert!(error(clock.utc_at(later).unwrap()) > Duration::from_secs(19 * 3600));
+    }
+
+    #[test]
+    fn test_utc_clock() {
+        let t0 = Instant::now();
+        let ms = |n| t0 + Duration::from_millis(n);
+        let mut clock = UtcClock::new();
+        assert_eq!(Duration::ZERO, clock.update(86399.0, ms(0)));
+        // 这条语句多延迟了 30 ms
+        assert!((clock.update(86400.0, ms(1030)).as_secs_f64() - 0.030).abs() < 1e-3);
+        assert!(clock.update(86401.0, ms(2000)).as_secs_f64() < 1e-3);
+        let t = clock.to_instant(86402.0).unwrap();
+        assert!(t.duration_since(ms(3000)).as_secs_f64() < 1e-3);
+    }
+}
@@ -0,0 +1,311 @@
+const SYNC: [u8; 2] = [0xb5, 0x62];
+
+/// UBX 消息
+#[derive(Clone, Debug)]
+pub enum UbxMessage {
+    NavPvt(NavPvt),
+    NavRelPosNed(NavRelPosNed),
+    NavSat(NavSat),
+    RxmRtcm(RxmRtcm),
+    /// 未支持的消息，保留类和号
+    Other {
+        class: u8,
+        id: u8,
+    },
+}
+
+/// NAV-PVT 导航解
+#[derive(Clone, Default, Debug)]
+pub struct NavPvt {
+    /// GPS 周内时（毫秒）
+    pub itow: u32,
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+    /// 秒的小数部分（纳秒），可能为负
+    pub nano: i32,
+    /// 日期和时间是否有效
+    pub valid_date: bool,
+    pub valid_time: bool,
+    /// 0 无，2 二维，3 三维，4 GNSS + 航位推算，5 仅时间
+    pub fix_type: u8,
+    pub fix_ok: bool,
+    /// 0 无，1 浮点解，2 固定解
+    pub carrier_solution: u8,
+    pub satellite: u8,
+    /// 度
+    pub longitude: f64,
+    pub latitude: f64,
+    /// 椭球高和海拔高（米）
+    pub height: f64,
+    pub altitude: f64,
+    /// 水平和垂直精度估计（米）
+    pub horizontal_accuracy: f32,
+    pub vertical_accuracy: f32,
+    /// 北东地速度（米每秒）
+    pub velocity: [f32; 3],
+    /// 运动方向（度）
+    pub heading_of_motion: f32,
+    pub pdop: f32,
+}
+
+/// NAV-RELPOSNED 相对基准站的位置，用于双天线定向
+#[derive(Clone, Default, Debug)]
+pub struct NavRelPosNed {
+    pub reference_station: u16,
+    pub itow: u32,
+    /// 北东地（米）
+    pub position: [f64; 3],
+    /// 基线长度（米），仅版本 1 提供
+    pub length: Option<f64>,
+    /// 基线航向（度），仅版本 1 提供
+    pub heading: Option<f32>,
+    /// 北东地精度（米）
+    pub accuracy: [f32; 3],
+    pub heading_accuracy: Option<f32>,
+    pub fix_ok: bool,
+    pub differential: bool,
+    pub position_valid: bool,
+    /// 0 无，1 浮点解，2 固定解
+    pub carrier_solution: u8,
+    pub heading_valid: bool,
+}
+
+/// NAV-SAT 卫星信息
+#[derive(Clone, Default, Debug)]
+pub struct NavSat {
+    pub itow: u32,
+    pub satellites: Vec<UbxSatellite>,
+}
+
+/// NAV-SAT 中的单颗卫星
+#[derive(Clone, Copy, Default, Debug)]
+pub struct UbxSatellite {
+    /// 0 GPS，1 SBAS，2 Galileo，3 北斗，5 QZSS，6 GLONASS
+    pub gnss_id: u8,
+    pub sv_id: u8,
+    /// 载噪比（dB-Hz）
+    pub cno: u8,
+    /// 仰角和方位角（度）
+    pub elevation: i8,
+    pub azimuth: i16,
+    /// 伪距残差（米）
+    pub residual: f32,
+    pub quality: u8,
+    pub used: bool,
+    pub differential: bool,
+}
+
+/// RXM-RTCM 收到的差分电文
+#[derive(Clone, Copy, Default, Debug)]
+pub struct RxmRtcm {
+    pub crc_failed: bool,
+    /// 0 未知，1 未使用，2 已使用
+    pub used: u8,
+    pub reference_station: u16,
+    pub message: u16,
+}
+
+/// 小端读取负载
+struct Payload<'a>(&'a [u8]);
+
+impl UbxMessage {
+    /// 从完整的帧中解析消息
+    pub fn decode(frame: &[u8]) -> Option<Self> {
+        let (class, id) = (*frame.get(2)?, *frame.get(3)?);
+        let p = Payload(frame.get(6..frame.len().checked_sub(2)?)?);
+        match (class, id) {
+            (0x01, 0x07) if p.0.len() >= 92 => {
+                let valid = p.u1(11);
+                let flags = p.u1(21);
+                Some(Self::NavPvt(NavPvt {
+                    itow: p.u4(0),
+                    year: p.u2(4),
+                    month: p.u1(6),
+                    day: p.u1(7),
+                    hour: p.u1(8),
+                    minute: p.u1(9),
+                    second: p.u1(10),
+                    nano: p.i4(16),
+                    valid_date: valid & 1 != 0,
+                    valid_time: valid & 2 != 0,
+                    fix_type: p.u1(20),
+                    fix_ok: flags & 1 != 0,
+                    carrier_solution: flags >> 6,
+                    satellite: p.u1(23),
+                    longitude: p.i4(24) as f64 * 1e-7,
+                    latitude: p.i4(28) as f64 * 1e-7,
+                    height: p.i4(32) as f64 * 1e-3,
+                    altitude: p.i4(36) as f64 * 1e-3,
+                    horizontal_accuracy: p.u4(40) as f32 * 1e-3,
+                    vertical_accuracy: p.u4(44) as f32 * 1e-3,
+                    velocity: [
+                        p.i4(48) as f32 * 1e-3,
+                        p.i4(52) as f32 * 1e-3,
+                        p.i4(56) as f32 * 1e-3,
+                    ],
+                    heading_of_motion: p.i4(64) as f32 * 1e-5,
+                    pdop: p.u2(76) as f32 * 0.01,
+                }))
+            }
+            (0x01, 0x3c) if p.0.len() >= 40 => {
+                // 版本 0 没有基线长度和航向
+                let v1 = p.u1(0) == 1 && p.0.len() >= 64;
+                let hp = if v1 { 32 } else { 20 };
+                let acc = if v1 { 36 } else { 24 };
+                let flags = p.u4(if v1 { 60 } else { 36 });
+                let position =
+                    |i: usize| p.i4(8 + i * 4) as f64 * 1e-2 + p.i1(hp + i) as f64 * 1e-4;
+                Some(Self::NavRelPosNed(NavRelPosNed {
+                    reference_station: p.u2(2),
+                    itow: p.u4(4),
+                    position: [position(0), position(1), position(2)],
+                    length: v1.then(|| p.i4(20) as f64 * 1e-2 + p.i1(35) as f64 * 1e-4),
+                    heading: v1.then(|| p.i4(24) as f32 * 1e-5),
+                    accuracy: [
+                        p.u4(acc) as f32 * 1e-4,
+                        p.u4(acc + 4) as f32 * 1e-4,
+                        p.u4(acc + 8) as f32 * 1e-4,
+                    ],
+                    heading_accuracy: v1.then(|| p.u4(52) as f32 * 1e-5),
+                    fix_ok: flags & 1 != 0,
+                    differential: flags & 2 != 0,
+                    position_valid: flags & 4 != 0,
+                    carrier_solution: ((flags >> 3) & 3) as u8,
+                    heading_valid: v1 && flags & 0x100 != 0,
+                }))
+            }
+            (0x01, 0x35) if p.0.len() >= 8 && p.0.len() >= 8 + p.u1(5) as usize * 12 => {
+                Some(Self::NavSat(NavSat {
+                    itow: p.u4(0),
+                    satellites: (0..p.u1(5) as usize)
+                        .map(|i| {
+                            let j = 8 + i * 12;
+                            let flags = p.u4(j + 8);
+                            UbxSatellite {
+                                gnss_id: p.u1(j),
+                                sv_id: p.u1(j + 1),
+                                cno: p.u1(j + 2),
+                                elevation: p.i1(j + 3),
+                                azimuth: p.i2(j + 4),
+                                residual: p.i2(j + 6) as f32 * 0.1,
+                                quality: (flags & 7) as u8,
+                                used: flags & 8 != 0,
+                                differential: flags & 0x40 != 0,
+                            }
+                        })
+                        .collect(),
+                }))
+            }
+            (0x02, 0x32) if p.0.len() >= 8 => Some(Self::RxmRtcm(RxmRtcm {
+                crc_failed: p.u1(1) & 1 != 0,
+                used: (p.u1(1) >> 1) & 3,
+                reference_station: p.u2(4),
+                message: p.u2(6),
+            })),
+            (0x01, 0x07) | (0x01, 0x3c) | (0x01, 0x35) | (0x02, 0x32) => None,
+            _ => Some(Self::Other { class, id }),
+        }
+    }
+}
+
+impl Payload<'_> {
+    #[inline]
+    fn u1(&self, i: usize) -> u8 {
+        self.0[i]
+    }
+
+    #[inline]
+    fn i1(&self, i: usize) -> i8 {
+        self.0[i] as i8
+    }
+
+    #[inline]
+    fn u2(&self, i: usize) -> u16 {
+        u16::from_le_bytes([self.0[i], self.0[i + 1]])
+    }
+
+    #[inline]
+    fn i2(&self, i: usize) -> i16 {
+        self.u2(i) as i16
+    }
+
+    #[inline]
+    fn u4(&self, i: usize) -> u32 {
+        u32::from_le_bytes([self.0[i], self.0[i + 1], self.0[i + 2], self.0[i + 3]])
+    }
+
+    #[inline]
+    fn i4(&self, i: usize) -> i32 {
+        self.u4(i) as i32
+    }
+}
+
+/// 组装 UBX 帧
+pub(crate) fn encode(class: u8, id: u8, payload: &[u8]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(payload.len() + 8);
+    frame.extend_from_slice(&SYNC);
+    frame.extend_from_slice(&[class, id]);
+    frame.extend_from_slice(&(payload.len() as u16).to_le_bytes());
+    frame.extend_from_slice(payload);
+    let ck = checksum(&frame[2..]);
+    frame.extend_from_slice(&ck);
+    frame
+}
+
+/// 8 位 Fletcher 校验
+pub(crate) fn checksum(buf: &[u8]) -> [u8; 2] {
+    buf.iter().fold([0u8, 0u8], |[a, b], x| {
+        let a = a.wrapping_add(*x);
+        [a, b.wrapping_add(a)]
+    })
+}
+
+#[cfg(test)]
+mod t {
+    use super::*;
+
+    #[test]
+    fn test_encode() {
+        // UBX-CFG-MSG 打开 GGA
+        assert_eq!(
+            vec![0xb5, 0x62, 0x06, 0x01, 0x03, 0x00, 0xf0, 0x00, 0x01, 0xfb, 0x10],
+            encode(0x06, 0x01, &[0xf0, 0x00, 0x01])
+        );
+    }
+
+    #[test]
+    fn test_decode() {
+        let frame = encode(0x02, 0x32, &[0x02, 0x04, 0, 0, 0x39, 0x05, 0xed, 0x03]);
+        match UbxMessage::decode(&frame) {
+            Some(UbxMessage::RxmRtcm(rtcm)) => {
+                assert!(!rtcm.crc_failed);
+                assert_eq!(2, rtcm.used);
+                assert_eq!(1337, rtcm.reference_station);
+                assert_eq!(1005, rtcm.message);
+            }
+            _ => panic!(),
+        }
+
+        let mut payload = [0u8; 64];
+        payload[0] = 1;
+        payload[20..24].copy_from_slice(&123i32.to_le_bytes());
+        payload[24..28].copy_from_slice(&9_000_000i32.to_le_bytes());
+        payload[35] = 45u8;
+        payload[60..64].copy_from_slice(&0x117u32.to_le_bytes());
+        match UbxMessage::decode(&encode(0x01, 0x3c, &payload)) {
+            Some(UbxMessage::NavRelPosNed(rel)) => {
+                assert!((rel.length.unwrap() - 1.2345).abs() < 1e-9);
+                assert_eq!(Some(90.0), rel.heading);
+                assert_eq!(2, rel.carrier_solution);
+                assert!(rel.heading_valid);
+            }
+            _ => panic!(),
+        }
+
+        assert!(UbxMessage::decode(&encode(0x01, 0x07, &[0; 10])).is_none());
+    }
+}
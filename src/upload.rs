@@ -0,0 +1,130 @@
+use crate::{network::GpggaSender, Gpgga, GpggaStatus};
+use async_std::{
+    sync::{Arc, Mutex, Weak},
+    task,
+};
+use std::time::{Duration, Instant};
+
+const TICK: Duration = Duration::from_millis(100);
+
+/// 默认上传周期
+pub const UPLOAD_INTERVAL: Duration = Duration::from_secs(5);
+
+/// 定时向差分服务器上传最近的有效位置
+///
+/// 板卡输出 GGA 的频率再高，每个周期也最多上传一次；
+/// 板卡停止输出时，继续上传最后的有效位置。连接后收到的第一个位置立即上传。
+#[derive(Clone)]
+pub struct GpggaUploader(Arc<Mutex<State>>);
+
+struct State {
+    interval: Duration,
+    latest: Option<String>,
+    sender: Option<GpggaSender>,
+    last_sent: Option<Instant>,
+}
+
+impl Default for GpggaUploader {
+    #[inline]
+    fn default() -> Self {
+        Self::new(UPLOAD_INTERVAL)
+    }
+}
+
+impl GpggaUploader {
+    /// 创建上传器并启动后台任务，所有副本释放后任务退出
+    pub fn new(interval: Duration) -> Self {
+        let state = Arc::new(Mutex::new(State {
+            interval,
+            latest: None,
+            sender: None,
+            last_sent: None,
+        }));
+        task::spawn(run(Arc::downgrade(&state)));
+        Self(state)
+    }
+
+    pub async fn set_interval(&self, interval: Duration) {
+        self.0.lock().await.interval = interval;
+    }
+
+    /// 连接或断开服务器，连接后重新计时
+    pub async fn set_sender(&self, sender: Option<GpggaSender>) {
+        let mut state = self.0.lock().await;
+        state.sender = sender;
+        state.last_sent = None;
+    }
+
+    /// 更新位置，没有定位的 GGA 被忽略，返回是否采用
+    pub async fn update(&self, gpgga: &Gpgga) -> bool {
+        let valid = gpgga.latitude.is_some()
+            && gpgga.longitude.is_some()
+            && gpgga.status != GpggaStatus::无效解;
+        if valid {
+            self.0.lock().await.latest = Some(format!("{}\r\n", gpgga));
+        }
+        valid
+    }
+}
+
+impl State {
+    fn due(&self, now: Instant) -> bool {
+        self.sender.is_some()
+            && self.latest.is_some()
+            && self.last_sent.is_none_or(|t| now >= t + self.interval)
+    }
+}
+
+async fn run(state: Weak<Mutex<State>>) {
+    loop {
+        task::sleep(TICK).await;
+        let state = match state.upgrade() {
+            Some(state) => state,
+            None => return,
+        };
+        let mut guard = state.lock().await;
+        let now = Instant::now();
+        if !guard.due(now) {
+            continue;
+        }
+        guard.last_sent = Some(now);
+        let line = guard.latest.clone().unwrap();
+        let mut sender = guard.sender.clone().unwrap();
+        // 写入可能在半开连接上阻塞，不能持有锁，否则板卡线程更新位置时也会阻塞
+        drop(guard);
+        drop(state);
+        sender.send(&line).await;
+    }
+}
+
+#[test]
+fn test_upload() {
+    use async_std::{io::ReadExt, net::TcpListener};
+
+    task::block_on(async {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let stream = async_std::net::TcpStream::connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+        let (mut caster, _) = listener.accept().await.unwrap();
+
+        let uploader = GpggaUploader::new(Duration::from_secs(60));
+        uploader.set_sender(Some(GpggaSender(stream))).await;
+        let line =
+            "$GPGGA,060220.00,3959.55874779,N,11619.61828897,E,1,17,1.6,60.1397,M,-9.2862,M,,*42";
+        assert!(
+            !uploader
+                .update(&"$GPGGA,,,,,,0,00,99.99,,,,,,*48".parse().unwrap())
+                .await
+        );
+        for _ in 0..10 {
+            assert!(uploader.update(&line.parse().unwrap()).await);
+        }
+        let mut buf = [0u8; 256];
+        let n = caster.read(&mut buf).await.unwrap();
+        assert_eq!(format!("{}\r\n", line).as_bytes(), &buf[..n]);
+        // 周期内不再上传
+        let more = async_std::io::timeout(Duration::from_millis(500), caster.read(&mut buf)).await;
+        assert!(more.is_err());
+    });
+}